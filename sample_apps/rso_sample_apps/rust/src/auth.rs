@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::async_trait;
+use axum::extract::{FromRef, FromRequestParts};
+use axum::http::header::{AUTHORIZATION, COOKIE};
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use log::{debug, error};
+
+use crate::handlers::data::{account_data, AccountData};
+use crate::http::Client;
+use crate::rate_limit::RateLimiter;
+use crate::routing::Platform;
+use crate::session::{self, Session, SessionStore};
+use crate::state::AppState;
+
+/// An authenticated caller.
+///
+/// Produced by the [`FromRequestParts`] extractor below, it is handed to handlers in place of a raw
+/// access-token string so that by the time a handler runs the token has already been confirmed to
+/// resolve to a valid Riot account.
+#[derive(Clone, Debug)]
+pub struct User {
+    /// The bearer token the caller authenticated with.
+    pub token: String,
+    /// The account the token resolved to, fetched once during introspection and cached.
+    pub account: AccountData,
+}
+
+/// How long a resolved account is trusted when the token carries no known expiry (i.e. a bearer
+/// token presented directly via the `Authorization` header), bounding how long a revoked token can
+/// keep resolving before introspection runs again.
+const DEFAULT_TTL: Duration = Duration::from_secs(300);
+
+/// A thread-safe cache of validated tokens to their resolved [`AccountData`].
+///
+/// Introspection calls Riot's account endpoint once per token; subsequent requests reuse the cached
+/// account so a burst from the same session does not re-hit the upstream. Each entry carries an
+/// expiry so a revoked or aged-out token is re-validated rather than resolving to a stale account
+/// forever, and expired entries are pruned so the map does not grow without bound. Clones share the
+/// same underlying map, mirroring how [`RateLimiter`](crate::rate_limit::RateLimiter) shares its
+/// state.
+#[derive(Clone, Default)]
+pub struct TokenCache {
+    inner: Arc<Mutex<HashMap<String, Entry>>>,
+}
+
+/// A cached account together with the instant at which it must be re-validated.
+struct Entry {
+    account: AccountData,
+    expiry: Instant,
+}
+
+impl TokenCache {
+    /// Creates an empty cache.
+    pub fn new() -> Self {
+        TokenCache::default()
+    }
+
+    /// Returns the account cached for `token` while it is still within its lifetime, dropping it on
+    /// expiry so the caller falls back to a fresh introspection.
+    fn get(&self, token: &str) -> Option<AccountData> {
+        let mut cache = self.inner.lock().unwrap();
+        match cache.get(token) {
+            Some(entry) if entry.expiry > Instant::now() => Some(entry.account.clone()),
+            Some(_) => {
+                cache.remove(token);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Caches the account resolved for `token` until `expiry`, first pruning any entries that have
+    /// aged out so the map tracks only live tokens.
+    fn insert(&self, token: String, account: AccountData, expiry: Instant) {
+        let mut cache = self.inner.lock().unwrap();
+        let now = Instant::now();
+        cache.retain(|_, entry| entry.expiry > now);
+        cache.insert(token, Entry { account, expiry });
+    }
+}
+
+/// The reasons token introspection can reject a request, each rendered as a `401`.
+#[derive(Debug)]
+pub enum AuthError {
+    /// No bearer token was supplied.
+    MissingToken,
+    /// The token did not resolve to a valid account.
+    InvalidToken,
+}
+
+impl IntoResponse for AuthError {
+    fn into_response(self) -> Response {
+        let message = match self {
+            AuthError::MissingToken => "unauthorized: missing access token",
+            AuthError::InvalidToken => "unauthorized: invalid access token",
+        };
+        (StatusCode::UNAUTHORIZED, message).into_response()
+    }
+}
+
+/// Resolves the caller's access token from the session cookie, refreshing it transparently when the
+/// stored session has expired.
+///
+/// A programmatic API client may instead present a bearer token directly via the `Authorization`
+/// header; this fallback is only consulted when no session cookie is present, so interactive
+/// sessions never carry a token in the request URL.
+async fn resolve_token(parts: &Parts, state: &AppState) -> Result<(String, Instant), AuthError> {
+    let sessions: SessionStore = FromRef::from_ref(state);
+    if let Some(id) = parts
+        .headers
+        .get(COOKIE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(session::session_id_from_cookies)
+    {
+        let Some(session) = sessions.get(&id) else {
+            debug!("🔑 unknown session cookie");
+            return Err(AuthError::InvalidToken);
+        };
+        if !session.is_expired() {
+            return Ok((session.access_token, cache_until(session.expiry)));
+        }
+
+        debug!("🔑 session expired, refreshing");
+        let client: Arc<dyn Client> = FromRef::from_ref(state);
+        let refreshed = session::refresh(
+            client.as_ref(),
+            &state.cfg,
+            &session.provider,
+            &session.refresh_token,
+        )
+            .await
+            .map_err(|e| {
+                error!("error refreshing session: {e}");
+                AuthError::InvalidToken
+            })?;
+        let token = refreshed.access_token.clone();
+        let expiry = cache_until(refreshed.expiry);
+        sessions.update(&id, refreshed);
+        return Ok((token, expiry));
+    }
+
+    // A directly-presented bearer token carries no expiry we can read, so trust it only for a
+    // bounded window before re-introspecting.
+    parts
+        .headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| (token.to_string(), Instant::now() + DEFAULT_TTL))
+        .ok_or(AuthError::MissingToken)
+}
+
+/// Translates a session's absolute expiry into the monotonic instant the cache should drop its
+/// entry, clamping a session that has already lapsed to "now".
+fn cache_until(expiry: chrono::DateTime<chrono::Utc>) -> Instant {
+    let remaining = (expiry - chrono::Utc::now())
+        .to_std()
+        .unwrap_or(Duration::ZERO);
+    Instant::now() + remaining
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for User {
+    type Rejection = AuthError;
+
+    /// Validates the caller's access token before any handler runs.
+    ///
+    /// The token is looked up in the [`TokenCache`] first; on a miss, Riot's account endpoint is
+    /// called once to confirm it resolves to a valid `puuid`, and the resulting [`AccountData`] is
+    /// cached for reuse. Any missing or non-resolving token is rejected with a `401`.
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let (token, expiry) = resolve_token(parts, state).await?;
+        if token.is_empty() {
+            return Err(AuthError::MissingToken);
+        }
+
+        if let Some(account) = state.token_cache.get(&token) {
+            debug!("🔑 reusing cached account for token");
+            return Ok(User { token, account });
+        }
+
+        // Account endpoints are region-agnostic for introspection, so resolve against the default
+        // regional cluster, reusing the shared, rate-limited account fetch.
+        let url = state.cfg.api.account_data_url(Platform::default());
+        let client: Arc<dyn Client> = FromRef::from_ref(state);
+        let limiter: RateLimiter = FromRef::from_ref(state);
+        let account = account_data(client.as_ref(), &url, &token, &limiter)
+            .await
+            .map_err(|e| {
+                error!("error validating access token: {e}");
+                AuthError::InvalidToken
+            })?;
+
+        state
+            .token_cache
+            .insert(token.clone(), account.clone(), expiry);
+        Ok(User { token, account })
+    }
+}
@@ -1,7 +1,16 @@
+use std::collections::HashMap;
+
 use config::{Config, Environment, File};
 use log::debug;
 use serde_derive::{Deserialize, Serialize};
 
+use crate::routing::Platform;
+use crate::secret::{ApiToken, ClientSecret};
+
+/// The provider key used when none is supplied, e.g. for a flat environment-variable configuration
+/// that describes a single RSO environment.
+pub const DEFAULT_PROVIDER: &str = "default";
+
 /// Represents the TLS configuration for the server.
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
 pub struct Tls {
@@ -27,8 +36,11 @@ pub struct Server {
 pub struct Configuration {
     /// Server configuration, including address and TLS settings.
     pub server: Server,
-    /// OAuth client configuration.
-    pub rso: Rso,
+    /// OAuth client configurations, keyed by a provider identifier (e.g. `prod`, `stage`).
+    ///
+    /// A single running instance can initiate and complete flows against any of these environments
+    /// by selecting a provider per request; see [`Configuration::sign_in_url`].
+    pub oauth: HashMap<String, Rso>,
     /// API endpoint configurations.
     pub api: Api,
 }
@@ -40,14 +52,20 @@ impl From<config::Config> for Configuration {
                 addr: cfg.get::<String>("SERVER_ADDRESS").unwrap_or_default(),
                 tls: cfg.get::<Option<Tls>>("SERVER_TLS").unwrap_or(None),
             },
-            rso: Rso {
-                base_url: cfg.get::<String>("RSO_BASE_URL").unwrap_or_default(),
-                callback_host: cfg.get::<String>("RSO_CALLBACK_HOST").unwrap_or_default(),
-                client_id: cfg.get::<String>("RSO_CLIENT_ID").unwrap_or_default(),
-                client_secret: cfg.get::<String>("RSO_CLIENT_SECRET").unwrap_or_default(),
-            },
+            oauth: HashMap::from([(
+                DEFAULT_PROVIDER.to_string(),
+                Rso {
+                    base_url: cfg.get::<String>("RSO_BASE_URL").unwrap_or_default(),
+                    callback_host: cfg.get::<String>("RSO_CALLBACK_HOST").unwrap_or_default(),
+                    client_id: cfg.get::<String>("RSO_CLIENT_ID").unwrap_or_default(),
+                    client_secret: cfg
+                        .get::<String>("RSO_CLIENT_SECRET")
+                        .unwrap_or_default()
+                        .into(),
+                },
+            )]),
             api: Api {
-                token: cfg.get::<String>("RGAPI_TOKEN").unwrap_or_default(),
+                token: cfg.get::<String>("RGAPI_TOKEN").unwrap_or_default().into(),
                 urls: Urls {
                     account_data: cfg
                         .get::<String>("RGAPI_URL_ACCOUNT_DATA")
@@ -71,67 +89,124 @@ pub struct Rso {
     /// Client ID for OAuth authentication.
     pub client_id: String,
     /// Client secret for OAuth authentication.
-    pub client_secret: String,
+    pub client_secret: ClientSecret,
 }
 
 /// Configuration for API endpoints.
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
 pub struct Api {
     /// Token for API authentication.
-    pub token: String,
+    pub token: ApiToken,
     /// URLs for different API endpoints.
     pub urls: Urls,
 }
 
+impl Api {
+    /// Builds the account-data URL for `platform`.
+    ///
+    /// Account endpoints live on the regional cluster nearest the player, so the `{host}` placeholder
+    /// in the configured template is filled with the platform's [`Region`](crate::routing::Region)
+    /// host (e.g. `americas`).
+    pub fn account_data_url(&self, platform: Platform) -> String {
+        self.urls
+            .account_data
+            .replace("{host}", platform.region().host())
+    }
+
+    /// Builds the champion-rotation URL for `platform`.
+    ///
+    /// Champion-rotation is a per-platform endpoint, so the `{host}` placeholder in the configured
+    /// template is filled with the platform host itself (e.g. `na1`).
+    pub fn champion_data_url(&self, platform: Platform) -> String {
+        self.urls.champion_data.replace("{host}", platform.host())
+    }
+}
+
 /// URLs for the API endpoints.
+///
+/// Each endpoint is a template containing a `{host}` placeholder that the routing layer fills with
+/// the correct regional or platform host per request, so a single configuration serves players in
+/// every region (see [`Api::account_data_url`] and [`Api::champion_data_url`]).
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
 pub struct Urls {
-    /// Endpoint for retrieving account data.
+    /// Endpoint template for retrieving account data, e.g.
+    /// `https://{host}.api.riotgames.com/riot/account/v1/accounts/me`.
     pub account_data: String,
-    /// Endpoint for retrieving champion data.
+    /// Endpoint template for retrieving champion data, e.g.
+    /// `https://{host}.api.riotgames.com/lol/platform/v3/champion-rotations`.
     pub champion_data: String,
 }
 
 impl Configuration {
-    /// Constructs the callback URL for OAuth provider redirection.
+    /// Returns the [`Rso`] configuration registered under `provider`, if any.
+    pub fn rso(&self, provider: &str) -> Option<&Rso> {
+        self.oauth.get(provider)
+    }
+
+    /// Constructs the callback URL for `provider`'s OAuth redirection.
+    ///
+    /// The provider key is carried in the path so it survives the round-trip back to `/oauth`, letting
+    /// the callback be completed against the same environment the flow was started on.
     ///
     /// # Returns
-    /// A `String` representing the full callback URL.
-    pub fn callback_url(&self) -> String {
+    /// The full callback URL, or `None` if `provider` is not configured.
+    pub fn callback_url(&self, provider: &str) -> Option<String> {
+        let rso = self.rso(provider)?;
         let protocol = match self.server.tls {
             Some(_) => "https://",
             None => "http://",
         };
-        format!("{}{}/oauth", protocol, self.rso.callback_host)
+        Some(format!(
+            "{}{}/oauth/{}",
+            protocol, rso.callback_host, provider
+        ))
     }
 
-    /// Constructs the token endpoint URL.
+    /// Constructs `provider`'s token endpoint URL.
     ///
     /// # Returns
-    /// A `String` representing the full token endpoint URL.
-    pub fn token_url(&self) -> String {
-        format!("{}/token", self.rso.base_url)
+    /// The full token endpoint URL, or `None` if `provider` is not configured.
+    pub fn token_url(&self, provider: &str) -> Option<String> {
+        Some(format!("{}/token", self.rso(provider)?.base_url))
     }
 
-    /// Constructs the authorization endpoint URL.
+    /// Constructs `provider`'s authorization endpoint URL.
     ///
     /// # Returns
-    /// A `String` representing the full authorization endpoint URL.
-    pub fn authorize_url(&self) -> String {
-        format!("{}/authorize", self.rso.base_url)
+    /// The full authorization endpoint URL, or `None` if `provider` is not configured.
+    pub fn authorize_url(&self, provider: &str) -> Option<String> {
+        Some(format!("{}/authorize", self.rso(provider)?.base_url))
     }
 
-    /// Constructs the sign-in URL with query parameters for OAuth authentication.
+    /// Builds the form body for a `refresh_token` grant against [`token_url`](Self::token_url).
+    ///
+    /// # Returns
+    /// The `(key, value)` pairs to POST, ready to be sent alongside the Basic client credentials.
+    pub fn refresh_token_request(&self, refresh_token: &str) -> Vec<(String, String)> {
+        vec![
+            ("grant_type".to_string(), "refresh_token".to_string()),
+            ("refresh_token".to_string(), refresh_token.to_string()),
+        ]
+    }
+
+    /// Constructs `provider`'s sign-in URL with query parameters for OAuth authentication.
+    ///
+    /// The caller supplies a freshly generated CSRF `state` and the S256 `code_challenge` derived
+    /// from its PKCE `code_verifier`; both are appended so the callback can be validated and the
+    /// verifier replayed in the token exchange.
     ///
     /// # Returns
-    /// A `String` representing the full sign-in URL.
-    pub fn sign_in_url(&self) -> String {
-        format!(
-            "{}?redirect_uri={}&client_id={}&response_type=code&scope=openid",
-            self.authorize_url(),
-            self.callback_url(),
-            self.rso.client_id,
-        )
+    /// The full sign-in URL, or `None` if `provider` is not configured.
+    pub fn sign_in_url(&self, provider: &str, state: &str, code_challenge: &str) -> Option<String> {
+        let rso = self.rso(provider)?;
+        Some(format!(
+            "{}?redirect_uri={}&client_id={}&response_type=code&scope=openid&state={}&code_challenge={}&code_challenge_method=S256",
+            self.authorize_url(provider)?,
+            self.callback_url(provider)?,
+            rso.client_id,
+            state,
+            code_challenge,
+        ))
     }
 }
 
@@ -178,18 +253,21 @@ mod tests {
                 tls: None,
             },
             api: Api {
-                token: "token".to_string(),
+                token: "token".into(),
                 urls: Urls {
                     account_data: "account_data".to_string(),
                     champion_data: "champion_data".to_string(),
                 },
             },
-            rso: Rso {
-                base_url: "base_url".to_string(),
-                callback_host: "local.example.com:8080".to_string(),
-                client_id: "client_id".to_string(),
-                client_secret: "client_secret".to_string(),
-            },
+            oauth: HashMap::from([(
+                DEFAULT_PROVIDER.to_string(),
+                Rso {
+                    base_url: "base_url".to_string(),
+                    callback_host: "local.example.com:8080".to_string(),
+                    client_id: "client_id".to_string(),
+                    client_secret: "client_secret".into(),
+                },
+            )]),
         }
     }
 
@@ -203,18 +281,21 @@ mod tests {
                 }),
             },
             api: Api {
-                token: "token".to_string(),
+                token: "token".into(),
                 urls: Urls {
                     account_data: "account_data".to_string(),
                     champion_data: "champion_data".to_string(),
                 },
             },
-            rso: Rso {
-                base_url: "base_url".to_string(),
-                callback_host: "local.example.com:8080".to_string(),
-                client_id: "client_id".to_string(),
-                client_secret: "client_secret".to_string(),
-            },
+            oauth: HashMap::from([(
+                DEFAULT_PROVIDER.to_string(),
+                Rso {
+                    base_url: "base_url".to_string(),
+                    callback_host: "local.example.com:8080".to_string(),
+                    client_id: "client_id".to_string(),
+                    client_secret: "client_secret".into(),
+                },
+            )]),
         }
     }
 
@@ -246,11 +327,12 @@ mod tests {
             .try_into()
             .unwrap();
         assert_eq!(c.server.addr, "SERVER_ADDRESS");
-        assert_eq!(c.rso.base_url, "RSO_BASE_URL");
-        assert_eq!(c.rso.callback_host, "RSO_CALLBACK_HOST");
-        assert_eq!(c.rso.client_id, "RSO_CLIENT_ID");
-        assert_eq!(c.rso.client_secret, "RSO_CLIENT_SECRET");
-        assert_eq!(c.api.token, "RGAPI_TOKEN");
+        let rso = c.rso(DEFAULT_PROVIDER).unwrap();
+        assert_eq!(rso.base_url, "RSO_BASE_URL");
+        assert_eq!(rso.callback_host, "RSO_CALLBACK_HOST");
+        assert_eq!(rso.client_id, "RSO_CLIENT_ID");
+        assert_eq!(rso.client_secret.secret(), "RSO_CLIENT_SECRET");
+        assert_eq!(c.api.token.secret(), "RGAPI_TOKEN");
         assert_eq!(c.api.urls.account_data, "RGAPI_URL_ACCOUNT_DATA");
         assert_eq!(c.api.urls.champion_data, "RGAPI_URL_CHAMPION_DATA");
     }
@@ -259,8 +341,8 @@ mod tests {
     fn test_sign_in_url() {
         let config = create_cfg();
         assert_eq!(
-            config.sign_in_url(),
-            "base_url/authorize?redirect_uri=http://local.example.com:8080/oauth&client_id=client_id&response_type=code&scope=openid",
+            config.sign_in_url(DEFAULT_PROVIDER, "state123", "challenge123").unwrap(),
+            "base_url/authorize?redirect_uri=http://local.example.com:8080/oauth/default&client_id=client_id&response_type=code&scope=openid&state=state123&code_challenge=challenge123&code_challenge_method=S256",
         );
     }
 
@@ -268,36 +350,78 @@ mod tests {
     fn test_sign_in_url_tls() {
         let config = create_cfg_tls();
         assert_eq!(
-            config.sign_in_url(),
-            "base_url/authorize?redirect_uri=https://local.example.com:8080/oauth&client_id=client_id&response_type=code&scope=openid",
+            config.sign_in_url(DEFAULT_PROVIDER, "state123", "challenge123").unwrap(),
+            "base_url/authorize?redirect_uri=https://local.example.com:8080/oauth/default&client_id=client_id&response_type=code&scope=openid&state=state123&code_challenge=challenge123&code_challenge_method=S256",
         );
     }
 
     #[test]
     fn test_callback_url() {
         let config = create_cfg();
-        assert_eq!(config.callback_url(), "http://local.example.com:8080/oauth");
+        assert_eq!(
+            config.callback_url(DEFAULT_PROVIDER).unwrap(),
+            "http://local.example.com:8080/oauth/default"
+        );
     }
 
     #[test]
     fn test_callback_url_tls() {
         let config = create_cfg_tls();
         assert_eq!(
-            config.callback_url(),
-            "https://local.example.com:8080/oauth"
+            config.callback_url(DEFAULT_PROVIDER).unwrap(),
+            "https://local.example.com:8080/oauth/default"
+        );
+    }
+
+    #[test]
+    fn account_data_url_routes_to_regional_cluster() {
+        use crate::routing::Platform;
+        let api = Api {
+            token: "token".into(),
+            urls: Urls {
+                account_data: "https://{host}.api.riotgames.com/riot/account/v1/accounts/me"
+                    .to_string(),
+                champion_data: "https://{host}.api.riotgames.com/lol/platform/v3/champion-rotations"
+                    .to_string(),
+            },
+        };
+        assert_eq!(
+            api.account_data_url(Platform::Euw1),
+            "https://europe.api.riotgames.com/riot/account/v1/accounts/me"
+        );
+    }
+
+    #[test]
+    fn champion_data_url_routes_to_platform_host() {
+        use crate::routing::Platform;
+        let api = Api {
+            token: "token".into(),
+            urls: Urls {
+                account_data: "https://{host}.api.riotgames.com/riot/account/v1/accounts/me"
+                    .to_string(),
+                champion_data: "https://{host}.api.riotgames.com/lol/platform/v3/champion-rotations"
+                    .to_string(),
+            },
+        };
+        assert_eq!(
+            api.champion_data_url(Platform::Euw1),
+            "https://euw1.api.riotgames.com/lol/platform/v3/champion-rotations"
         );
     }
 
     #[test]
     fn test_token_url() {
         let config = create_cfg();
-        assert_eq!(config.token_url(), "base_url/token");
+        assert_eq!(config.token_url(DEFAULT_PROVIDER).unwrap(), "base_url/token");
     }
 
     #[test]
     fn test_authorize_url() {
         let config = create_cfg();
-        assert_eq!(config.authorize_url(), "base_url/authorize");
+        assert_eq!(
+            config.authorize_url(DEFAULT_PROVIDER).unwrap(),
+            "base_url/authorize"
+        );
     }
 
     #[test]
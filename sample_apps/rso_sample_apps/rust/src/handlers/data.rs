@@ -1,10 +1,18 @@
-use super::HtmlTemplate;
+use super::{wants_json, HtmlTemplate};
+use crate::auth::User;
 use crate::config::Configuration;
+use crate::http::{Client, Response as HttpResponse};
+use crate::rate_limit::RateLimiter;
+use crate::routing::Platform;
 use askama::Template;
 use axum::extract::{Query, State};
+use axum::http::{HeaderMap, StatusCode};
 use axum::response::IntoResponse;
+use axum::Json;
 use log::{debug, error, info};
 use serde_derive::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
 
 /// AccountData represents the account data of a user
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -69,21 +77,90 @@ impl std::fmt::Display for ChampionRotationData {
 ///     Err(e) => println!("An error occurred: {}", e),
 /// }
 /// ```
-fn account_data(url: &str, token: &str) -> core::result::Result<AccountData, String> {
+pub(crate) async fn account_data(
+    client: &dyn Client,
+    url: &str,
+    token: &str,
+    limiter: &RateLimiter,
+) -> core::result::Result<AccountData, String> {
     debug!("requesting account data");
-    match ureq::get(url)
-        .set("Authorization", format!("Bearer {token}").as_str())
-        .call()
-    {
-        Ok(res) => {
-            debug!("successfully requested account data");
-            Ok(serde_json::from_str(res.into_string().unwrap().as_mut_str()).unwrap())
-        }
-        Err(e) => {
-            error!("error getting account data: {e}");
-            Err(e.to_string())
+    for attempt in 0..=MAX_RETRIES {
+        limiter.acquire(url).await;
+        let res = client
+            .get(url, vec![("Authorization".into(), format!("Bearer {token}"))])
+            .await
+            .map_err(|e| {
+                error!("error getting account data: {e}");
+                e.to_string()
+            })?;
+        match check_response(limiter, url, res.as_ref()) {
+            Disposition::Ok => {
+                debug!("successfully requested account data");
+                return res.into_json().await.map_err(|e| e.to_string());
+            }
+            Disposition::Throttled if attempt < MAX_RETRIES => {
+                debug!("retrying account data after rate-limit backoff");
+            }
+            Disposition::Throttled => return Err("upstream rate limit exceeded".to_string()),
+            Disposition::Failed(e) => return Err(e),
         }
     }
+    unreachable!("retry loop returns on every terminal disposition")
+}
+
+/// Updates the shared [`RateLimiter`] from the rate-limit headers on a response so its buckets
+/// self-correct to the server's view.
+fn observe_limits(limiter: &RateLimiter, endpoint: &str, res: &dyn HttpResponse) {
+    limiter.observe(
+        endpoint,
+        res.header("X-App-Rate-Limit").as_deref(),
+        res.header("X-App-Rate-Limit-Count").as_deref(),
+        res.header("X-Method-Rate-Limit").as_deref(),
+        res.header("X-Method-Rate-Limit-Count").as_deref(),
+    );
+}
+
+/// The maximum number of times a throttled request is retried after honouring the `429` cool-down
+/// before giving up.
+const MAX_RETRIES: usize = 2;
+
+/// What to do with an upstream response after inspecting its status.
+enum Disposition {
+    /// A success status; the body is ready to decode.
+    Ok,
+    /// A `429`; the offending scope has been blocked for its `Retry-After` and the caller may retry
+    /// once the cool-down has elapsed.
+    Throttled,
+    /// A non-retryable upstream failure, carrying the message to surface.
+    Failed(String),
+}
+
+/// Feeds the rate limiter from the response headers and classifies the status.
+///
+/// On a `429` the offending scope is blocked for the `Retry-After` duration the provider reported so
+/// the next [`RateLimiter::acquire`] backs off exactly that long before the request is retried.
+fn check_response(limiter: &RateLimiter, endpoint: &str, res: &dyn HttpResponse) -> Disposition {
+    observe_limits(limiter, endpoint, res);
+    let status = res.status();
+    if status == 429 {
+        let scope = res
+            .header("X-Rate-Limit-Type")
+            .as_deref()
+            .and_then(RateLimiter::scope_from_header)
+            .unwrap_or(crate::rate_limit::Scope::Application);
+        let retry_after = res
+            .header("Retry-After")
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .unwrap_or(1);
+        limiter.penalize(scope, Duration::from_secs(retry_after));
+        return Disposition::Throttled;
+    }
+    if (200..300).contains(&status) {
+        Disposition::Ok
+    } else {
+        error!("upstream returned status {status}");
+        Disposition::Failed(format!("upstream returned status {status}"))
+    }
 }
 
 /// Fetches the champion rotation data.
@@ -110,21 +187,35 @@ fn account_data(url: &str, token: &str) -> core::result::Result<AccountData, Str
 ///     Err(e) => println!("An error occurred: {}", e),
 /// }
 /// ```
-fn champion_rotation_data(
+async fn champion_rotation_data(
+    client: &dyn Client,
     url: &str,
     token: &str,
+    limiter: &RateLimiter,
 ) -> core::result::Result<ChampionRotationData, String> {
     debug!("requesting champion rotation data");
-    match ureq::get(url).set("X-Riot-Token", token).call() {
-        Ok(res) => {
-            debug!("successfully requested champion rotation data");
-            Ok(serde_json::from_str(res.into_string().unwrap().as_mut_str()).unwrap())
-        }
-        Err(e) => {
-            error!("error getting champion data: {e}");
-            Err(e.to_string())
+    for attempt in 0..=MAX_RETRIES {
+        limiter.acquire(url).await;
+        let res = client
+            .get(url, vec![("X-Riot-Token".into(), token.to_string())])
+            .await
+            .map_err(|e| {
+                error!("error getting champion data: {e}");
+                e.to_string()
+            })?;
+        match check_response(limiter, url, res.as_ref()) {
+            Disposition::Ok => {
+                debug!("successfully requested champion rotation data");
+                return res.into_json().await.map_err(|e| e.to_string());
+            }
+            Disposition::Throttled if attempt < MAX_RETRIES => {
+                debug!("retrying champion rotation data after rate-limit backoff");
+            }
+            Disposition::Throttled => return Err("upstream rate limit exceeded".to_string()),
+            Disposition::Failed(e) => return Err(e),
         }
     }
+    unreachable!("retry loop returns on every terminal disposition")
 }
 
 /// Represents a request containing an access token.
@@ -134,9 +225,11 @@ fn champion_rotation_data(
 /// to authorize requests to protected resources.
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Request {
-    /// The access token that was given to us after the user authenticated with the
-    /// provider. This token is used to authenticate requests made to the API.
-    pub access_token: String,
+    /// The platform shard the player belongs to (e.g. `na1`, `euw1`, `kr`). It selects the platform
+    /// host for champion-rotation and the regional cluster for account data. Defaults to `na1` when
+    /// the client does not provide one.
+    #[serde(default)]
+    pub region: String,
 }
 
 /// Represents the response sent to the client for data requests.
@@ -164,123 +257,193 @@ pub struct Response {
     pub message: String,
 }
 
-/// Handles data requests by fetching account and champion rotation data.
+/// The machine-readable form of [`Response`].
+///
+/// Returned when the client negotiates `application/json`. It drops the pre-stringified
+/// `account_data`/`champion_rotation_data` fields that only exist to feed the HTML template, since a
+/// JSON consumer already has the structured `account` and `champion_rotation` objects.
+#[derive(Serialize)]
+pub struct JsonResponse {
+    /// The account data retrieved for the user.
+    pub account: AccountData,
+    /// The champion rotation data retrieved from the game server.
+    pub champion_rotation: ChampionRotationData,
+    /// An optional message that can be included in the response.
+    pub message: String,
+}
+
+/// Handles data requests by fetching champion rotation data for an authenticated user.
 ///
-/// This asynchronous function acts as a handler for incoming data requests. It first checks if the provided
-/// access token is empty, returning an error if so. If the access token is present, it proceeds to fetch
-/// both account data and champion rotation data using the provided access token and configuration settings.
-/// Upon successful retrieval of both data sets, it constructs a `Response` object containing the fetched data
-/// and returns it wrapped in an `HtmlTemplate` for rendering.
+/// Authentication happens up-front in the [`User`] extractor, which introspects the caller's access
+/// token against Riot's account endpoint; by the time this handler runs the account is already known,
+/// so it only needs to fetch champion rotation data and combine it with the user's account before
+/// rendering it as an `HtmlTemplate`, or as JSON when the client negotiates `application/json`.
 ///
 /// # Arguments
-/// * `query` - Extracted query parameters from the request, containing the access token.
-/// * `cfg` - Application configuration state, containing URLs and tokens for data fetching.
+/// * `user` - The authenticated caller, carrying the account their token resolved to.
+/// * `query` - Extracted query parameters, selecting the platform shard to route to.
+/// * `cfg` - Application configuration state, containing URL templates and tokens.
 ///
 /// # Returns
-/// A result wrapped in `impl IntoResponse`, which on success contains an `HtmlTemplate<Response>` with the fetched data,
-/// or an error string if the access token is missing or data fetching fails.
-///
-/// # Errors
-/// Returns an error if the access token is empty or if there is an issue fetching the account or champion rotation data.
+/// An `impl IntoResponse`. On success it is an `HtmlTemplate<Response>` (or a JSON body when negotiated)
+/// with the fetched data; an unknown region answers `400 Bad Request` and an upstream fetch failure
+/// answers `502 Bad Gateway` without echoing the upstream error to the client.
 ///
 pub async fn handle(
+    user: User,
     Query(query): Query<Request>,
+    headers: HeaderMap,
     State(cfg): State<Configuration>,
+    State(limiter): State<RateLimiter>,
+    State(client): State<Arc<dyn Client>>,
 ) -> impl IntoResponse {
-    if query.access_token.is_empty() {
-        return Err("unauthorized".to_string());
-    }
+    // Resolve the platform shard from the request, defaulting to `na1` when unspecified, and reject
+    // anything we do not recognise with a `400` before issuing any upstream call.
+    let platform = if query.region.is_empty() {
+        Platform::default()
+    } else {
+        match query.region.parse::<Platform>() {
+            Ok(platform) => platform,
+            Err(e) => {
+                error!("rejecting data request: {e}");
+                return (StatusCode::BAD_REQUEST, e).into_response();
+            }
+        }
+    };
 
     info!("☁️ handling data request");
 
-    // Fetch champion rotation data using the provided access token. This operation may block the thread.
-    let champion_data = champion_rotation_data(&cfg.api.urls.champion_data, &cfg.api.token)
-        .map_err(|e| format!("{:?}", e))?;
-
-    // Fetch account data using the provided access token. This operation may block the thread.
-    let acct_data = account_data(&cfg.api.urls.account_data, &query.access_token)
-        .map_err(|e| format!("{:?}", e))?;
+    // Fetch champion rotation data from the platform host.
+    let champion_data = match champion_rotation_data(
+        client.as_ref(),
+        &cfg.api.champion_data_url(platform),
+        cfg.api.token.secret(),
+        &limiter,
+    )
+    .await
+    {
+        Ok(data) => data,
+        Err(e) => {
+            error!("error fetching champion rotation data: {e}");
+            return (StatusCode::BAD_GATEWAY, "error fetching champion rotation data").into_response();
+        }
+    };
 
     info!("☁️ completed handling data request");
 
-    // Create a `Response` object with the account and champion rotation data.
-    Ok(HtmlTemplate(Response {
-        account: acct_data.clone(),
-        account_data: acct_data.clone().to_string(),
-        champion_rotation: champion_data.clone(),
-        champion_rotation_data: champion_data.clone().to_string(),
-        message: "".to_string(),
-    }))
+    // Serve JSON to API consumers and the rendered HTML template to browsers.
+    if wants_json(&headers) {
+        Json(JsonResponse {
+            account: user.account,
+            champion_rotation: champion_data,
+            message: "".to_string(),
+        })
+        .into_response()
+    } else {
+        HtmlTemplate(Response {
+            account_data: user.account.to_string(),
+            account: user.account,
+            champion_rotation_data: champion_data.to_string(),
+            champion_rotation: champion_data,
+            message: "".to_string(),
+        })
+        .into_response()
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::config::{Api, Configuration, Rso, Tls, Urls};
-    use crate::handlers::data::{account_data, champion_rotation_data};
+    use super::{account_data, champion_rotation_data};
+    use crate::http::{BoxFuture, Client, Error, Response as HttpResponse};
+    use crate::rate_limit::RateLimiter;
+
+    /// A canned [`HttpResponse`] that yields a fixed status and body with no network I/O.
+    struct MockResponse {
+        status: u16,
+        body: &'static str,
+    }
+
+    impl HttpResponse for MockResponse {
+        fn status(&self) -> u16 {
+            self.status
+        }
 
-    fn create_cfg_api_url(url: String) -> Configuration {
-        Configuration {
-            server: crate::config::Server {
-                addr: "0.0.0.0:443".to_string(),
-                tls: Some(Tls {
-                    cert: "cert".to_string(),
-                    key: "key".to_string(),
-                }),
-            },
-            api: Api {
-                token: "token".to_string(),
-                urls: Urls {
-                    account_data: url.clone(),
-                    champion_data: url.clone(),
-                },
-            },
-            rso: Rso {
-                base_url: "base_url".to_string(),
-                callback_host: "local.example.com:8080".to_string(),
-                client_id: "client_id".to_string(),
-                client_secret: "client_secret".to_string(),
-            },
+        fn header(&self, _name: &str) -> Option<String> {
+            None
+        }
+
+        fn bytes(self: Box<Self>) -> BoxFuture<'static, Result<Vec<u8>, Error>> {
+            Box::pin(async move { Ok(self.body.as_bytes().to_vec()) })
         }
     }
 
-    #[test]
-    fn account_data_returns_expected_result() {
-        let api = mock::ApiProvider::new();
-        let cfg = create_cfg_api_url(api.server.url("/riot/account/v1/accounts/me").to_string());
-        let res = account_data(&cfg.api.urls.account_data, "token");
-        assert_eq!(false, res.is_err());
+    /// A [`Client`] that always answers with a fixed status and body, letting the handlers' decoding
+    /// and error handling be exercised without spinning up a mock HTTP server.
+    struct MockClient {
+        status: u16,
+        body: &'static str,
+    }
+
+    impl Client for MockClient {
+        fn get(
+            &self,
+            _url: &str,
+            _headers: Vec<(String, String)>,
+        ) -> BoxFuture<'_, Result<Box<dyn HttpResponse>, Error>> {
+            let res = MockResponse {
+                status: self.status,
+                body: self.body,
+            };
+            Box::pin(async move { Ok(Box::new(res) as Box<dyn HttpResponse>) })
+        }
+
+        fn post_form(
+            &self,
+            _url: &str,
+            _headers: Vec<(String, String)>,
+            _form: Vec<(String, String)>,
+        ) -> BoxFuture<'_, Result<Box<dyn HttpResponse>, Error>> {
+            unimplemented!("the data handlers only issue GET requests")
+        }
     }
 
-    #[test]
-    fn account_data_handles_error() {
-        let api = mock::ApiProvider::new();
-        let cfg = create_cfg_api_url(api.server.url("/riot/account/v1/accounts/me").to_string());
-        let res = account_data(&cfg.api.urls.account_data, "");
+    #[tokio::test]
+    async fn account_data_returns_expected_result() {
+        let client = MockClient {
+            status: 200,
+            body: r#"{"puuid":"123","game_name":"user","tag_line":"tag"}"#,
+        };
+        let res = account_data(&client, "account", "token", &RateLimiter::new()).await;
+        assert_eq!(false, res.is_err());
+    }
 
+    #[tokio::test]
+    async fn account_data_handles_error() {
+        let client = MockClient {
+            status: 401,
+            body: r#"{}"#,
+        };
+        let res = account_data(&client, "account", "", &RateLimiter::new()).await;
         assert_eq!(true, res.is_err());
     }
 
     #[tokio::test]
     async fn champion_rotation_data_returns_expected_result() {
-        let api = mock::ApiProvider::new();
-        let cfg = create_cfg_api_url(
-            api.server
-                .url("/lol/platform/v3/champion-rotations")
-                .to_string(),
-        );
-        let res = champion_rotation_data(&cfg.api.urls.champion_data, "token");
+        let client = MockClient {
+            status: 200,
+            body: r#"{"free_champion_ids":[1,2,3],"free_champion_ids_for_new_players":[100,101,102],"max_new_player_level":10}"#,
+        };
+        let res = champion_rotation_data(&client, "champion", "token", &RateLimiter::new()).await;
         assert_eq!(false, res.is_err());
     }
 
     #[tokio::test]
     async fn champion_rotation_data_handles_error() {
-        let api = mock::ApiProvider::new();
-        let cfg = create_cfg_api_url(
-            api.server
-                .url("/lol/platform/v3/champion-rotations")
-                .to_string(),
-        );
-        let res = champion_rotation_data(&cfg.api.urls.champion_data, "");
+        let client = MockClient {
+            status: 401,
+            body: r#"{}"#,
+        };
+        let res = champion_rotation_data(&client, "champion", "", &RateLimiter::new()).await;
         assert_eq!(true, res.is_err());
     }
 }
@@ -1,20 +1,33 @@
-use super::HtmlTemplate;
-use crate::config::Configuration;
+use super::{wants_json, HtmlTemplate};
+use crate::config::{Configuration, DEFAULT_PROVIDER};
+use crate::pkce::{self, PkceStore};
 use askama::Template;
-use axum::extract::State;
+use axum::extract::{Query, State};
+use axum::http::{HeaderMap, StatusCode};
 use axum::response::IntoResponse;
+use axum::Json;
+use serde::{Deserialize, Serialize};
 
 /// Represents the server's response to a request with a sign-in URL.
 ///
 /// This struct is used to generate the HTML response for the client, directing them to the sign-in page.
-/// It leverages the Askama template engine to render the `default.html` template with the provided `sign_in_url`.
-#[derive(Template, Clone)]
+/// It leverages the Askama template engine to render the `default.html` template with the provided `sign_in_url`,
+/// and serializes to JSON for clients that negotiate `application/json`.
+#[derive(Template, Clone, Serialize)]
 #[template(path = "default.html")]
 pub struct Response {
     /// The URL to which the user should be redirected for signing in.
     sign_in_url: String,
 }
 
+/// Query parameters accepted by the landing handler.
+#[derive(Deserialize)]
+pub struct Params {
+    /// The RSO provider to start the flow against; defaults to [`DEFAULT_PROVIDER`] when omitted.
+    #[serde(default)]
+    provider: Option<String>,
+}
+
 /// Handles requests by generating a response with a sign-in URL.
 ///
 /// This asynchronous function is an Axum handler that constructs a `Response` struct with the sign-in URL
@@ -26,10 +39,32 @@ pub struct Response {
 ///
 /// # Returns
 /// An implementation of `IntoResponse`, which Axum can convert into an HTTP response to be sent to the client.
-pub async fn handle(State(cfg): State<Configuration>) -> impl IntoResponse {
-    let res = Response {
-        sign_in_url: cfg.sign_in_url(),
+pub async fn handle(
+    Query(params): Query<Params>,
+    State(cfg): State<Configuration>,
+    State(pkce): State<PkceStore>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let provider = params
+        .provider
+        .unwrap_or_else(|| DEFAULT_PROVIDER.to_string());
+
+    // Mint a PKCE verifier and CSRF state for this flow, remembering the verifier so the callback
+    // can replay it, and advertise only the derived challenge in the URL.
+    let verifier = pkce::generate_verifier();
+    let state = pkce::generate_state();
+    let challenge = pkce::challenge(&verifier);
+
+    let Some(sign_in_url) = cfg.sign_in_url(&provider, &state, &challenge) else {
+        return (StatusCode::BAD_REQUEST, format!("unknown provider '{provider}'")).into_response();
     };
+    pkce.insert(state, verifier);
+
+    let res = Response { sign_in_url };
 
-    HtmlTemplate(res)
+    if wants_json(&headers) {
+        Json(res).into_response()
+    } else {
+        HtmlTemplate(res).into_response()
+    }
 }
@@ -1,11 +1,24 @@
 use askama::Template;
-use axum::http::StatusCode;
+use axum::http::header::ACCEPT;
+use axum::http::{HeaderMap, StatusCode};
 use axum::response::{Html, IntoResponse};
 
 pub mod data;
 pub mod default;
 pub mod oauth;
 
+/// Returns whether the client asked for a JSON response via the `Accept` header.
+///
+/// Used by the handlers to decide between rendering an Askama template and serializing the response
+/// as JSON, letting the service double as a machine-readable API.
+pub(crate) fn wants_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.contains("application/json"))
+        .unwrap_or(false)
+}
+
 /// A wrapper struct for Askama templates to facilitate their conversion into Axum responses.
 ///
 /// This struct takes a generic type `T` which must implement the `Template` trait from the Askama crate.
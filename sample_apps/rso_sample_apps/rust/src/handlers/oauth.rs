@@ -1,12 +1,20 @@
-use super::HtmlTemplate;
+use super::{wants_json, HtmlTemplate};
 use crate::config::Configuration;
+use crate::http::Client;
+use crate::pkce::PkceStore;
+use crate::secret::{AccessToken, RefreshToken};
+use crate::session::{self, Session, SessionStore};
 use askama::Template;
 use axum::{
-    extract::{Query, State},
-    response::IntoResponse,
+    extract::{Path, Query, State},
+    http::header::SET_COOKIE,
+    http::{HeaderMap, StatusCode},
+    response::{AppendHeaders, IntoResponse, Response as AxumResponse},
+    Json,
 };
 use base64::prelude::*;
-use log::info;
+use log::{error, info, warn};
+use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
 
@@ -19,6 +27,9 @@ use serde::{Deserialize, Serialize};
 pub struct Request {
     /// The authorization code provided by the OAuth provider.
     pub code: String,
+    /// The CSRF `state` echoed back by the provider, matched against the value we minted when
+    /// building the sign-in URL to protect against authorization-code injection and CSRF.
+    pub state: String,
 }
 
 /// Represents the OAuth2 response returned from the authorization server.
@@ -30,9 +41,9 @@ pub struct Request {
 #[template(path = "oauth.html")]
 pub struct Response {
     /// The OAuth2 access token.
-    pub access_token: String,
+    pub access_token: AccessToken,
     /// The  OAuth2 refresh token.
-    pub refresh_token: String,
+    pub refresh_token: RefreshToken,
     /// The OAuth2 scope.
     pub scope: String,
     /// The OAuth2 ID token.
@@ -51,6 +62,8 @@ pub struct Response {
 /// response into an HTML template using the `HtmlTemplate` wrapper.
 ///
 /// # Arguments
+/// * `Path(provider)` - The RSO provider key carried in the callback path, selecting which
+///   configured environment to complete the exchange against.
 /// * `Query(query)` - The extracted query parameters containing the authorization code.
 /// * `State(cfg)` - The application configuration state, containing OAuth client credentials
 ///   and endpoints.
@@ -60,24 +73,132 @@ pub struct Response {
 /// sent back to the client. This response includes the OAuth tokens rendered into an HTML
 /// template.
 pub async fn handle(
+    Path(provider): Path<String>,
     Query(query): Query<Request>,
+    headers: HeaderMap,
     State(cfg): State<Configuration>,
+    State(client): State<Arc<dyn Client>>,
+    State(pkce): State<PkceStore>,
+    State(sessions): State<SessionStore>,
 ) -> impl IntoResponse {
     info!("✍️ handling oauth request");
-    let code = query.code;
-    let form = [
-        ("grant_type", "authorization_code"),
-        ("code", code.as_str()),
-        ("redirect_uri", &cfg.callback_url()),
+
+    // The callback must name a configured provider; otherwise we have no credentials or endpoints to
+    // complete the exchange against.
+    let Some(rso) = cfg.rso(&provider) else {
+        warn!("✍️ rejecting oauth callback for unknown provider '{provider}'");
+        return (StatusCode::BAD_REQUEST, format!("unknown provider '{provider}'"))
+            .into_response();
+    };
+
+    // Recover the PKCE verifier for this `state`; an unknown or expired state means the callback did
+    // not originate from a sign-in we started, so reject it before touching the token endpoint.
+    let Some(code_verifier) = pkce.take(&query.state) else {
+        warn!("✍️ rejecting oauth callback with unknown state");
+        return (StatusCode::BAD_REQUEST, "invalid or expired state").into_response();
+    };
+
+    let Some(callback_url) = cfg.callback_url(&provider) else {
+        warn!("✍️ rejecting oauth callback for unknown provider '{provider}'");
+        return (StatusCode::BAD_REQUEST, format!("unknown provider '{provider}'"))
+            .into_response();
+    };
+    let form = vec![
+        ("grant_type".to_string(), "authorization_code".to_string()),
+        ("code".to_string(), query.code),
+        ("redirect_uri".to_string(), callback_url),
+        ("code_verifier".to_string(), code_verifier),
     ];
-    let auth = BASE64_STANDARD.encode(format!("{}:{}", cfg.rso.client_id, cfg.rso.client_secret));
-    let res: Response = ureq::post(cfg.token_url().as_str())
-        .set("Authorization", format!("Basic {auth}").as_str())
-        .send_form(&form)
-        .expect("error sending token request")
-        .into_json()
-        .expect("error parsing oauth response");
+    let auth = BASE64_STANDARD.encode(format!(
+        "{}:{}",
+        rso.client_id,
+        rso.client_secret.secret()
+    ));
+
+    let token_url = cfg
+        .token_url(&provider)
+        .expect("provider validated above");
+    let res = match client
+        .post_form(
+            &token_url,
+            vec![("Authorization".into(), format!("Basic {auth}"))],
+            form,
+        )
+        .await
+    {
+        Ok(res) => res,
+        Err(e) => {
+            error!("✍️ error sending token request: {e}");
+            return token_error(
+                &headers,
+                StatusCode::BAD_GATEWAY,
+                format!("error contacting token endpoint: {e}"),
+            );
+        }
+    };
+
+    // A non-2xx status carries the provider's error (e.g. `invalid_grant` for an expired code);
+    // surface its status and body instead of panicking so the user sees the real reason.
+    let status = res.status();
+    if !(200..300).contains(&status) {
+        let body = match res.bytes().await {
+            Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+            Err(e) => format!("error reading token endpoint response: {e}"),
+        };
+        warn!("✍️ token endpoint returned {status}: {body}");
+        let code = StatusCode::from_u16(status).unwrap_or(StatusCode::BAD_GATEWAY);
+        return token_error(&headers, code, body);
+    }
+
+    let res: Response = match res.into_json().await {
+        Ok(res) => res,
+        Err(e) => {
+            error!("✍️ error parsing oauth response: {e}");
+            return token_error(
+                &headers,
+                StatusCode::BAD_GATEWAY,
+                format!("error parsing token endpoint response: {e}"),
+            );
+        }
+    };
+
+    // Store the full token set server-side and hand the browser only an opaque session cookie, so
+    // the access and refresh tokens never travel in a URL.
+    let id = sessions.insert(Session::from_response(&res, provider));
+    // Mark the cookie `Secure` when the server is serving over TLS, so the opaque session id is never
+    // sent back over a plaintext connection.
+    let secure = if cfg.server.tls.is_some() {
+        "; Secure"
+    } else {
+        ""
+    };
+    let cookie = format!(
+        "{}={}; HttpOnly; Path=/; SameSite=Lax{}",
+        session::COOKIE_NAME,
+        id,
+        secure
+    );
     info!("✍️ completed handling oauth request");
 
-    HtmlTemplate(res)
+    (AppendHeaders([(SET_COOKIE, cookie)]), HtmlTemplate(res)).into_response()
+}
+
+/// Builds a content-negotiated error response carrying the upstream `status` and `message`.
+///
+/// API consumers that asked for `application/json` get a structured `{ "error": ... }` body; browsers
+/// get the message as plain text. Either way the worker stays alive and the caller sees the provider's
+/// actual error rather than a dropped connection.
+fn token_error(headers: &HeaderMap, status: StatusCode, message: String) -> AxumResponse {
+    if wants_json(headers) {
+        (status, Json(ErrorResponse { error: message })).into_response()
+    } else {
+        (status, message).into_response()
+    }
+}
+
+/// The JSON body returned to API consumers when the token exchange fails.
+#[derive(Serialize)]
+struct ErrorResponse {
+    /// The provider's error message or a description of the local failure.
+    error: String,
 }
@@ -0,0 +1,143 @@
+use std::future::Future;
+use std::pin::Pin;
+
+use serde::de::DeserializeOwned;
+
+/// A boxed, `Send` future, used as the return type of the async [`Client`] methods so the trait
+/// stays object-safe and can be stored behind an `Arc<dyn Client>`.
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// An error raised while performing an outbound HTTP request or decoding its body.
+#[derive(Debug)]
+pub struct Error(pub String);
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error(e.to_string())
+    }
+}
+
+/// An abstraction over an HTTP response, independent of the underlying client implementation.
+///
+/// The trait is deliberately object-safe so responses can be returned as `Box<dyn Response>`; the
+/// generic `into_json` decoder lives in an inherent impl on the trait object rather than on the
+/// trait itself.
+pub trait Response: Send {
+    /// The HTTP status code of the response.
+    fn status(&self) -> u16;
+    /// Returns the value of a response header, if present.
+    fn header(&self, name: &str) -> Option<String>;
+    /// Consumes the response and yields its raw body bytes.
+    fn bytes(self: Box<Self>) -> BoxFuture<'static, Result<Vec<u8>, Error>>;
+}
+
+impl dyn Response {
+    /// Consumes the response and deserializes its JSON body into `T`.
+    pub async fn into_json<T: DeserializeOwned>(self: Box<Self>) -> Result<T, Error> {
+        let body = self.bytes().await?;
+        serde_json::from_slice(&body).map_err(|e| Error(e.to_string()))
+    }
+}
+
+/// An async, swappable HTTP client.
+///
+/// A single implementation is stored in the application state and shared across handlers, replacing
+/// the direct blocking `ureq` calls that previously stalled the Tokio worker. The default
+/// [`ReqwestClient`] talks to the real Riot endpoints; tests inject their own implementation to
+/// return canned responses without standing up a server.
+pub trait Client: Send + Sync {
+    /// Issues a `GET` request with the given headers.
+    fn get(
+        &self,
+        url: &str,
+        headers: Vec<(String, String)>,
+    ) -> BoxFuture<'_, Result<Box<dyn Response>, Error>>;
+
+    /// Issues a `POST` request with a form-urlencoded body and the given headers.
+    fn post_form(
+        &self,
+        url: &str,
+        headers: Vec<(String, String)>,
+        form: Vec<(String, String)>,
+    ) -> BoxFuture<'_, Result<Box<dyn Response>, Error>>;
+}
+
+/// The default [`Client`] implementation, backed by `reqwest`.
+#[derive(Clone, Default)]
+pub struct ReqwestClient {
+    inner: reqwest::Client,
+}
+
+impl ReqwestClient {
+    /// Creates a new client with reqwest's default configuration.
+    pub fn new() -> Self {
+        ReqwestClient::default()
+    }
+}
+
+/// Copies the supplied `(name, value)` pairs onto a reqwest request builder.
+fn with_headers(
+    mut builder: reqwest::RequestBuilder,
+    headers: Vec<(String, String)>,
+) -> reqwest::RequestBuilder {
+    for (name, value) in headers {
+        builder = builder.header(name, value);
+    }
+    builder
+}
+
+impl Client for ReqwestClient {
+    fn get(
+        &self,
+        url: &str,
+        headers: Vec<(String, String)>,
+    ) -> BoxFuture<'_, Result<Box<dyn Response>, Error>> {
+        let request = with_headers(self.inner.get(url), headers);
+        Box::pin(async move {
+            let res = request.send().await?;
+            Ok(Box::new(ReqwestResponse(res)) as Box<dyn Response>)
+        })
+    }
+
+    fn post_form(
+        &self,
+        url: &str,
+        headers: Vec<(String, String)>,
+        form: Vec<(String, String)>,
+    ) -> BoxFuture<'_, Result<Box<dyn Response>, Error>> {
+        let request = with_headers(self.inner.post(url), headers).form(&form);
+        Box::pin(async move {
+            let res = request.send().await?;
+            Ok(Box::new(ReqwestResponse(res)) as Box<dyn Response>)
+        })
+    }
+}
+
+/// A [`Response`] wrapping a `reqwest::Response`.
+struct ReqwestResponse(reqwest::Response);
+
+impl Response for ReqwestResponse {
+    fn status(&self) -> u16 {
+        self.0.status().as_u16()
+    }
+
+    fn header(&self, name: &str) -> Option<String> {
+        self.0
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(ToString::to_string)
+    }
+
+    fn bytes(self: Box<Self>) -> BoxFuture<'static, Result<Vec<u8>, Error>> {
+        Box::pin(async move { Ok(self.0.bytes().await?.to_vec()) })
+    }
+}
@@ -0,0 +1,228 @@
+use std::process::Command;
+
+use base64::prelude::*;
+use log::{debug, error, info, warn};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+use crate::config::Configuration;
+use crate::handlers::oauth;
+use crate::http::{Client, ReqwestClient};
+use crate::pkce;
+
+/// The minimal HTML shown in the browser once the redirect has been captured.
+const DONE_PAGE: &str = "<!doctype html><html><body><p>Login complete. \
+You can close this tab and return to your terminal.</p></body></html>";
+
+/// Performs a single interactive RSO login from the terminal.
+///
+/// Binds a one-shot loopback listener on `provider`'s `callback_host` port, prints (and, unless
+/// `no_open`, opens) the sign-in URL, waits for the single OAuth redirect to land on `/oauth`,
+/// exchanges the authorization code for tokens, and prints the resulting access token — redacted
+/// unless `show_token` is set. The listener accepts exactly one connection and is dropped when the
+/// function returns, so nothing is left running afterwards.
+///
+/// # Errors
+///
+/// Returns a descriptive error if the provider is unknown, the listener cannot bind, the redirect
+/// fails the CSRF `state` check, or the token exchange does not succeed.
+pub async fn run(
+    cfg: &Configuration,
+    provider: &str,
+    show_token: bool,
+    no_open: bool,
+) -> Result<(), String> {
+    let rso = cfg
+        .rso(provider)
+        .ok_or_else(|| format!("unknown provider '{provider}'"))?;
+    let port = rso
+        .callback_host
+        .rsplit_once(':')
+        .map(|(_, port)| port)
+        .ok_or_else(|| format!("callback_host '{}' has no port to bind", rso.callback_host))?;
+
+    let listener = TcpListener::bind(format!("127.0.0.1:{port}"))
+        .await
+        .map_err(|e| format!("error binding loopback listener on port {port}: {e}"))?;
+
+    // Mint a PKCE verifier and CSRF state for this single flow; we keep the verifier locally rather
+    // than in the shared store the web service uses, since there is exactly one flow in flight.
+    let verifier = pkce::generate_verifier();
+    let state = pkce::generate_state();
+    let challenge = pkce::challenge(&verifier);
+    let sign_in_url = cfg
+        .sign_in_url(provider, &state, &challenge)
+        .ok_or_else(|| format!("unknown provider '{provider}'"))?;
+
+    println!("Open this URL to sign in:\n{sign_in_url}");
+    if !no_open {
+        open_browser(&sign_in_url);
+    }
+
+    info!("🔐 waiting for the oauth redirect on port {port}");
+    let request = accept_redirect(&listener).await?;
+    let (code, returned_state) = parse_callback(&request)?;
+    if returned_state != state {
+        return Err("oauth redirect carried an unexpected state".to_string());
+    }
+
+    let client = ReqwestClient::new();
+    let token = exchange_code(&client, cfg, provider, rso, &code, &verifier).await?;
+
+    if show_token {
+        println!("access_token: {}", token.access_token);
+        println!("refresh_token: {}", token.refresh_token);
+    } else {
+        println!("access_token: {:?}", token.access_token);
+        println!("refresh_token: {:?}", token.refresh_token);
+    }
+    info!("🔐 login complete");
+    Ok(())
+}
+
+/// Accepts a single connection and returns the first line of the HTTP request it carries.
+async fn accept_redirect(listener: &TcpListener) -> Result<String, String> {
+    let (mut stream, _) = listener
+        .accept()
+        .await
+        .map_err(|e| format!("error accepting oauth redirect: {e}"))?;
+
+    let mut buf = [0u8; 4096];
+    let read = stream
+        .read(&mut buf)
+        .await
+        .map_err(|e| format!("error reading oauth redirect: {e}"))?;
+    let request = String::from_utf8_lossy(&buf[..read]).into_owned();
+
+    let body = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        DONE_PAGE.len(),
+        DONE_PAGE
+    );
+    if let Err(e) = stream.write_all(body.as_bytes()).await {
+        warn!("🔐 error writing redirect response: {e}");
+    }
+    Ok(request)
+}
+
+/// Extracts the `code` and `state` query parameters from the request line's `/oauth` target.
+fn parse_callback(request: &str) -> Result<(String, String), String> {
+    let target = request
+        .split_whitespace()
+        .nth(1)
+        .ok_or_else(|| "malformed oauth redirect request".to_string())?;
+    let query = target
+        .split_once('?')
+        .map(|(_, query)| query)
+        .ok_or_else(|| "oauth redirect carried no query parameters".to_string())?;
+
+    let mut code = None;
+    let mut state = None;
+    for (key, value) in query.split('&').filter_map(|pair| pair.split_once('=')) {
+        match key {
+            "code" => code = Some(value.to_string()),
+            "state" => state = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    match (code, state) {
+        (Some(code), Some(state)) => Ok((code, state)),
+        _ => Err("oauth redirect missing code or state".to_string()),
+    }
+}
+
+/// Exchanges the captured authorization `code` for tokens at `provider`'s token endpoint.
+async fn exchange_code(
+    client: &dyn Client,
+    cfg: &Configuration,
+    provider: &str,
+    rso: &crate::config::Rso,
+    code: &str,
+    verifier: &str,
+) -> Result<oauth::Response, String> {
+    let callback_url = cfg
+        .callback_url(provider)
+        .ok_or_else(|| format!("unknown provider '{provider}'"))?;
+    let token_url = cfg
+        .token_url(provider)
+        .ok_or_else(|| format!("unknown provider '{provider}'"))?;
+    let form = vec![
+        ("grant_type".to_string(), "authorization_code".to_string()),
+        ("code".to_string(), code.to_string()),
+        ("redirect_uri".to_string(), callback_url),
+        ("code_verifier".to_string(), verifier.to_string()),
+    ];
+    let auth = BASE64_STANDARD.encode(format!(
+        "{}:{}",
+        rso.client_id,
+        rso.client_secret.secret()
+    ));
+
+    let res = client
+        .post_form(
+            &token_url,
+            vec![("Authorization".into(), format!("Basic {auth}"))],
+            form,
+        )
+        .await
+        .map_err(|e| format!("error contacting token endpoint: {e}"))?;
+
+    let status = res.status();
+    if !(200..300).contains(&status) {
+        let body = res
+            .bytes()
+            .await
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+            .unwrap_or_else(|e| format!("error reading token endpoint response: {e}"));
+        error!("🔐 token endpoint returned {status}: {body}");
+        return Err(format!("token exchange failed with status {status}: {body}"));
+    }
+
+    res.into_json()
+        .await
+        .map_err(|e| format!("error parsing token endpoint response: {e}"))
+}
+
+/// Best-effort attempt to open `url` in the developer's default browser.
+///
+/// Failures are logged and ignored: the URL has already been printed, so a missing opener just means
+/// the developer follows the link themselves.
+fn open_browser(url: &str) {
+    let opener = if cfg!(target_os = "macos") {
+        "open"
+    } else if cfg!(target_os = "windows") {
+        "explorer"
+    } else {
+        "xdg-open"
+    };
+    match Command::new(opener).arg(url).spawn() {
+        Ok(_) => debug!("🔐 opened sign-in URL with {opener}"),
+        Err(e) => debug!("🔐 could not open a browser automatically ({opener}): {e}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_callback_extracts_code_and_state() {
+        let request = "GET /oauth/default?code=abc&state=xyz HTTP/1.1\r\nHost: localhost\r\n\r\n";
+        let (code, state) = parse_callback(request).unwrap();
+        assert_eq!(code, "abc");
+        assert_eq!(state, "xyz");
+    }
+
+    #[test]
+    fn parse_callback_rejects_request_without_query() {
+        let request = "GET /oauth/default HTTP/1.1\r\n\r\n";
+        assert!(parse_callback(request).is_err());
+    }
+
+    #[test]
+    fn parse_callback_rejects_missing_code() {
+        let request = "GET /oauth/default?state=xyz HTTP/1.1\r\n\r\n";
+        assert!(parse_callback(request).is_err());
+    }
+}
@@ -1,8 +1,18 @@
 use clap::Parser;
 use log::{debug, info};
+mod auth;
 mod config;
 mod handlers;
+mod http;
+mod login;
+mod pkce;
+mod rate_limit;
+mod routing;
+mod secret;
 mod service;
+mod session;
+mod state;
+mod token;
 
 /// Struct containing the command line arguments.
 ///
@@ -22,6 +32,33 @@ pub struct Args {
     /// `config.yml`
     #[arg(short, long, default_value = "config.yml")]
     pub config: String,
+
+    /// Perform a single interactive login from the terminal instead of starting the server.
+    ///
+    /// Binds a one-shot loopback listener on the selected provider's callback port, prints and
+    /// opens the sign-in URL, captures the OAuth redirect, and prints the resulting access token.
+    ///
+    /// # Short and long options
+    ///
+    /// - `-l`, `--login`
+    #[arg(short, long)]
+    pub login: bool,
+
+    /// The RSO provider to log in against when using `--login`.
+    ///
+    /// # Default value
+    ///
+    /// `default`
+    #[arg(short, long, default_value = "default")]
+    pub provider: String,
+
+    /// Print the captured tokens in full instead of redacting them.
+    #[arg(long)]
+    pub show_token: bool,
+
+    /// Do not attempt to open the sign-in URL in a browser; just print it.
+    #[arg(long)]
+    pub no_open: bool,
 }
 
 /// The main entry point for the program.
@@ -40,9 +77,16 @@ async fn main() {
     let args = Args::parse();
     debug!("😀 parsed command line arguments: {args:?}");
     match config::parse(args.config) {
-        // If the configuration file is successfully parsed, start the service.
+        // If the configuration file is successfully parsed, either run a single login or start the
+        // long-running service.
         Ok(cfg) => {
-            service::listen(&cfg).await;
+            if args.login {
+                if let Err(err) = login::run(&cfg, &args.provider, args.show_token, args.no_open).await {
+                    panic!("{err}")
+                }
+            } else {
+                service::listen(&cfg).await;
+            }
         }
         // If the configuration file  is not successfully parsed, panic.
         Err(err) => {
@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use base64::prelude::*;
+use chrono::{DateTime, Duration, Utc};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+/// How long an unused `(state -> code_verifier)` mapping stays valid before a callback is rejected.
+const TTL_MINUTES: i64 = 10;
+
+/// Generates a PKCE `code_verifier`.
+///
+/// 32 random octets base64url-encoded without padding yields a 43-character string drawn entirely
+/// from the unreserved set, satisfying RFC 7636's 43–128 character requirement.
+pub fn generate_verifier() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    BASE64_URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Generates a random, opaque CSRF `state` value.
+pub fn generate_state() -> String {
+    let mut bytes = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    BASE64_URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Computes the S256 `code_challenge` for a verifier: `base64url_nopad(SHA256(code_verifier))`.
+pub fn challenge(verifier: &str) -> String {
+    let digest = Sha256::digest(verifier.as_bytes());
+    BASE64_URL_SAFE_NO_PAD.encode(digest)
+}
+
+/// A short-TTL store mapping a pending CSRF `state` to its PKCE `code_verifier`.
+///
+/// The sign-in handler stores the mapping when it builds the authorize URL; the callback consumes it
+/// to recover the verifier and to prove the request it received is one it started. Clones share the
+/// same map, matching the other pieces of application state.
+#[derive(Clone, Default)]
+pub struct PkceStore {
+    inner: Arc<Mutex<HashMap<String, Entry>>>,
+}
+
+/// A stored verifier together with the instant it was created, for TTL enforcement.
+struct Entry {
+    verifier: String,
+    created: DateTime<Utc>,
+}
+
+impl PkceStore {
+    /// Creates an empty store.
+    pub fn new() -> Self {
+        PkceStore::default()
+    }
+
+    /// Records the `code_verifier` for a freshly minted `state`.
+    pub fn insert(&self, state: String, verifier: String) {
+        self.inner.lock().unwrap().insert(
+            state,
+            Entry {
+                verifier,
+                created: Utc::now(),
+            },
+        );
+    }
+
+    /// Removes and returns the verifier for `state`, provided it exists and has not expired.
+    ///
+    /// Consuming the entry ensures a `state` is single-use, and the TTL check rejects stale
+    /// callbacks.
+    pub fn take(&self, state: &str) -> Option<String> {
+        let entry = self.inner.lock().unwrap().remove(state)?;
+        if Utc::now() - entry.created > Duration::minutes(TTL_MINUTES) {
+            None
+        } else {
+            Some(entry.verifier)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verifier_length_is_within_spec() {
+        let verifier = generate_verifier();
+        assert!((43..=128).contains(&verifier.len()));
+    }
+
+    #[test]
+    fn challenge_matches_known_vector() {
+        // The canonical example from RFC 7636 Appendix B.
+        assert_eq!(
+            challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+
+    #[test]
+    fn take_consumes_the_state() {
+        let store = PkceStore::new();
+        store.insert("state".to_string(), "verifier".to_string());
+        assert_eq!(store.take("state"), Some("verifier".to_string()));
+        assert_eq!(store.take("state"), None);
+    }
+
+    #[test]
+    fn take_rejects_unknown_state() {
+        let store = PkceStore::new();
+        assert_eq!(store.take("nope"), None);
+    }
+}
@@ -0,0 +1,328 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use log::{debug, warn};
+
+/// The scope a Riot rate limit applies to.
+///
+/// Riot advertises two limit dimensions on every successful response and tells us which one we
+/// tripped on a `429` via the `X-Rate-Limit-Type` header. The `service` scope is only ever seen on
+/// a `429` (it has no advertised buckets) and is treated as an opaque cool-down.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Scope {
+    /// The per-application limit, advertised via `X-App-Rate-Limit`.
+    Application,
+    /// The per-method limit, advertised via `X-Method-Rate-Limit`.
+    Method,
+    /// An underlying service limit, only surfaced on a `429`.
+    Service,
+}
+
+impl Scope {
+    /// Parses the value of a `429` `X-Rate-Limit-Type` header into a [`Scope`].
+    fn from_limit_type(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "application" => Some(Scope::Application),
+            "method" => Some(Scope::Method),
+            "service" => Some(Scope::Service),
+            _ => None,
+        }
+    }
+}
+
+/// A single advertised bucket: at most `limit` requests may be issued inside any `window`.
+///
+/// Requests are tracked as a sliding window of timestamps so we can compute exactly how long to
+/// wait for the oldest request in a saturated window to age out.
+#[derive(Debug, Clone)]
+struct Bucket {
+    limit: usize,
+    window: Duration,
+    hits: VecDeque<Instant>,
+}
+
+impl Bucket {
+    fn new(limit: usize, window: Duration) -> Self {
+        Bucket {
+            limit,
+            window,
+            hits: VecDeque::new(),
+        }
+    }
+
+    /// Drops every timestamp that has aged out of the window as of `now`.
+    fn expire(&mut self, now: Instant) {
+        while let Some(&oldest) = self.hits.front() {
+            if now.duration_since(oldest) >= self.window {
+                self.hits.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Returns how long to sleep before another request fits, or `None` if there is room now.
+    fn wait(&mut self, now: Instant) -> Option<Duration> {
+        self.expire(now);
+        if self.hits.len() < self.limit {
+            return None;
+        }
+        self.hits
+            .front()
+            .map(|oldest| self.window - now.duration_since(*oldest))
+    }
+}
+
+/// Shared, self-correcting rate limiter that keeps us just under Riot's dynamic ceilings.
+///
+/// The limiter learns its buckets from the `X-App-Rate-Limit` / `X-Method-Rate-Limit` headers on
+/// every response and reconciles its local counters against the `*-Count` headers so its view
+/// tracks the server's. Clones share the same underlying state, so a single limiter can be stored
+/// in the Axum application state and handed to every handler, mirroring how the Riven client shares
+/// one limiter across calls.
+#[derive(Clone)]
+pub struct RateLimiter {
+    inner: Arc<Mutex<State>>,
+}
+
+#[derive(Default)]
+struct State {
+    /// Sliding-window buckets keyed by `(scope, endpoint, window-seconds)`.
+    buckets: HashMap<(Scope, String, u64), Bucket>,
+    /// Scopes that are blocked until the given instant, following a `429`.
+    blocked: HashMap<Scope, Instant>,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RateLimiter {
+    /// Creates an empty limiter with no buckets learned yet.
+    pub fn new() -> Self {
+        RateLimiter {
+            inner: Arc::new(Mutex::new(State::default())),
+        }
+    }
+
+    /// Blocks until it is safe to issue a request against `endpoint`.
+    ///
+    /// Respects any active `429` cool-down first, then honours every advertised bucket for the
+    /// endpoint, sleeping until the oldest request in a saturated window expires. Finally it records
+    /// the outgoing request against each bucket so concurrent callers see the reservation.
+    ///
+    /// The lock is only ever held while computing the next wait, never across the `await`, so a
+    /// throttling limiter yields the Tokio worker instead of blocking it.
+    pub async fn acquire(&self, endpoint: &str) {
+        loop {
+            let wait = {
+                let mut state = self.inner.lock().unwrap();
+                let now = Instant::now();
+
+                // A `429` cool-down dominates everything else.
+                if let Some(wait) = state.blocked_wait(now) {
+                    Some(wait)
+                } else {
+                    state.bucket_wait(endpoint, now)
+                }
+            };
+
+            match wait {
+                Some(wait) if !wait.is_zero() => {
+                    debug!("⏳ rate limiter sleeping {:?} before {endpoint}", wait);
+                    tokio::time::sleep(wait).await;
+                }
+                _ => break,
+            }
+        }
+
+        let mut state = self.inner.lock().unwrap();
+        state.record(endpoint, Instant::now());
+    }
+
+    /// Reconciles the limiter with the buckets and counts Riot reported on a response.
+    pub fn observe(
+        &self,
+        endpoint: &str,
+        app_limit: Option<&str>,
+        app_count: Option<&str>,
+        method_limit: Option<&str>,
+        method_count: Option<&str>,
+    ) {
+        let mut state = self.inner.lock().unwrap();
+        let now = Instant::now();
+        state.reconcile(Scope::Application, endpoint, app_limit, app_count, now);
+        state.reconcile(Scope::Method, endpoint, method_limit, method_count, now);
+    }
+
+    /// Blocks the given `scope` for `retry_after`, as instructed by a `429` response.
+    pub fn penalize(&self, scope: Scope, retry_after: Duration) {
+        let mut state = self.inner.lock().unwrap();
+        warn!("🚦 rate limited on {scope:?}, backing off for {retry_after:?}");
+        state
+            .blocked
+            .insert(scope, Instant::now() + retry_after);
+    }
+
+    /// Parses a `429`'s `X-Rate-Limit-Type` header into the scope to penalize.
+    pub fn scope_from_header(value: &str) -> Option<Scope> {
+        Scope::from_limit_type(value)
+    }
+}
+
+impl State {
+    /// Returns the longest remaining cool-down across blocked scopes, if any.
+    fn blocked_wait(&mut self, now: Instant) -> Option<Duration> {
+        self.blocked.retain(|_, until| *until > now);
+        self.blocked
+            .values()
+            .map(|until| *until - now)
+            .max()
+    }
+
+    /// Returns the longest wait required across every bucket for `endpoint`.
+    fn bucket_wait(&mut self, endpoint: &str, now: Instant) -> Option<Duration> {
+        self.buckets
+            .iter_mut()
+            .filter(|((_, ep, _), _)| ep == endpoint)
+            .filter_map(|(_, bucket)| bucket.wait(now))
+            .max()
+    }
+
+    /// Records an outgoing request against every bucket for `endpoint`.
+    fn record(&mut self, endpoint: &str, now: Instant) {
+        for ((_, ep, _), bucket) in self.buckets.iter_mut() {
+            if ep == endpoint {
+                bucket.hits.push_back(now);
+            }
+        }
+    }
+
+    /// Learns the buckets for `scope`/`endpoint` from a limit header and pads the local counters up
+    /// to the server-reported counts so the limiter self-corrects to the server's view.
+    fn reconcile(
+        &mut self,
+        scope: Scope,
+        endpoint: &str,
+        limit_header: Option<&str>,
+        count_header: Option<&str>,
+        now: Instant,
+    ) {
+        let Some(limits) = limit_header.map(parse_windows) else {
+            return;
+        };
+        let counts: HashMap<u64, usize> = count_header
+            .map(parse_windows)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(count, window_secs)| (window_secs, count))
+            .collect();
+
+        for (count, window_secs) in limits {
+            let key = (scope, endpoint.to_string(), window_secs);
+            let window = Duration::from_secs(window_secs);
+            let bucket = self
+                .buckets
+                .entry(key)
+                .or_insert_with(|| Bucket::new(count, window));
+            bucket.limit = count;
+            bucket.window = window;
+            bucket.expire(now);
+
+            // If the server has seen more requests in this window than we have tracked locally,
+            // pad our sliding window so we back off in line with the server's accounting.
+            if let Some(&server_count) = counts.get(&window_secs) {
+                while bucket.hits.len() < server_count {
+                    bucket.hits.push_back(now);
+                }
+            }
+        }
+    }
+}
+
+/// Parses a Riot rate-limit header of the form `count:seconds,count:seconds` into `(count, seconds)`
+/// pairs, skipping any malformed segment.
+fn parse_windows(header: &str) -> Vec<(usize, u64)> {
+    header
+        .split(',')
+        .filter_map(|segment| {
+            let (count, seconds) = segment.trim().split_once(':')?;
+            Some((count.trim().parse().ok()?, seconds.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_windows_parses_multiple_buckets() {
+        assert_eq!(parse_windows("20:1,100:120"), vec![(20, 1), (100, 120)]);
+    }
+
+    #[test]
+    fn parse_windows_skips_malformed_segments() {
+        assert_eq!(parse_windows("20:1,garbage,100:120"), vec![(20, 1), (100, 120)]);
+    }
+
+    #[test]
+    fn scope_from_header_is_case_insensitive() {
+        assert_eq!(Scope::from_limit_type("Application"), Some(Scope::Application));
+        assert_eq!(Scope::from_limit_type("method"), Some(Scope::Method));
+        assert_eq!(Scope::from_limit_type("SERVICE"), Some(Scope::Service));
+        assert_eq!(Scope::from_limit_type("nonsense"), None);
+    }
+
+    #[test]
+    fn bucket_reports_no_wait_with_room() {
+        let mut bucket = Bucket::new(2, Duration::from_secs(60));
+        let now = Instant::now();
+        bucket.hits.push_back(now);
+        assert!(bucket.wait(now).is_none());
+    }
+
+    #[test]
+    fn bucket_reports_wait_when_saturated() {
+        let mut bucket = Bucket::new(1, Duration::from_secs(60));
+        let now = Instant::now();
+        bucket.hits.push_back(now);
+        let wait = bucket.wait(now).expect("saturated bucket should wait");
+        assert!(wait <= Duration::from_secs(60));
+    }
+
+    #[test]
+    fn observe_learns_buckets_and_pads_counts() {
+        let limiter = RateLimiter::new();
+        limiter.observe(
+            "account",
+            Some("20:1,100:120"),
+            Some("20:1,50:120"),
+            None,
+            None,
+        );
+        let state = limiter.inner.lock().unwrap();
+        let one_sec = state
+            .buckets
+            .get(&(Scope::Application, "account".to_string(), 1))
+            .expect("1s bucket learned");
+        assert_eq!(one_sec.limit, 20);
+        assert_eq!(one_sec.hits.len(), 20);
+    }
+
+    #[test]
+    fn penalize_blocks_then_clears() {
+        let limiter = RateLimiter::new();
+        limiter.penalize(Scope::Service, Duration::from_millis(50));
+        {
+            let mut state = limiter.inner.lock().unwrap();
+            assert!(state.blocked_wait(Instant::now()).is_some());
+        }
+        std::thread::sleep(Duration::from_millis(60));
+        let mut state = limiter.inner.lock().unwrap();
+        assert!(state.blocked_wait(Instant::now()).is_none());
+    }
+}
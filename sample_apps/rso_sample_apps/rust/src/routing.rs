@@ -0,0 +1,146 @@
+use std::str::FromStr;
+
+/// A Riot *regional* routing cluster.
+///
+/// Account-centric endpoints (account-v1 and friends) are served from the regional host nearest the
+/// player — `americas`, `asia`, or `europe` — rather than from a platform shard.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Region {
+    /// The Americas cluster, serving the NA, BR, and LAN/LAS platforms.
+    Americas,
+    /// The Asia cluster, serving the KR, JP, and OCE platforms.
+    Asia,
+    /// The Europe cluster, serving the EUW, EUNE, TR, and RU platforms.
+    Europe,
+}
+
+impl Region {
+    /// The host label used in a regional API URL, e.g. `americas` in
+    /// `https://americas.api.riotgames.com`.
+    pub fn host(&self) -> &'static str {
+        match self {
+            Region::Americas => "americas",
+            Region::Asia => "asia",
+            Region::Europe => "europe",
+        }
+    }
+}
+
+/// A Riot *platform* shard.
+///
+/// League endpoints such as champion-rotations are served per platform (`na1`, `euw1`, `kr`, …).
+/// Every platform maps onto exactly one regional [`Region`] cluster, which is where the player's
+/// account endpoints live.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Platform {
+    /// North America.
+    Na1,
+    /// Brazil.
+    Br1,
+    /// Latin America North.
+    La1,
+    /// Latin America South.
+    La2,
+    /// Oceania.
+    Oc1,
+    /// Europe West.
+    Euw1,
+    /// Europe Nordic & East.
+    Eun1,
+    /// Türkiye.
+    Tr1,
+    /// Russia.
+    Ru,
+    /// Korea.
+    Kr,
+    /// Japan.
+    Jp1,
+}
+
+impl Platform {
+    /// The host label used in a platform API URL, e.g. `na1`.
+    pub fn host(&self) -> &'static str {
+        match self {
+            Platform::Na1 => "na1",
+            Platform::Br1 => "br1",
+            Platform::La1 => "la1",
+            Platform::La2 => "la2",
+            Platform::Oc1 => "oc1",
+            Platform::Euw1 => "euw1",
+            Platform::Eun1 => "eun1",
+            Platform::Tr1 => "tr1",
+            Platform::Ru => "ru",
+            Platform::Kr => "kr",
+            Platform::Jp1 => "jp1",
+        }
+    }
+
+    /// The regional cluster that serves account endpoints for this platform.
+    pub fn region(&self) -> Region {
+        match self {
+            Platform::Na1 | Platform::Br1 | Platform::La1 | Platform::La2 => Region::Americas,
+            Platform::Kr | Platform::Jp1 | Platform::Oc1 => Region::Asia,
+            Platform::Euw1 | Platform::Eun1 | Platform::Tr1 | Platform::Ru => Region::Europe,
+        }
+    }
+}
+
+impl Default for Platform {
+    /// Defaults to North America when a caller does not specify a platform.
+    fn default() -> Self {
+        Platform::Na1
+    }
+}
+
+impl FromStr for Platform {
+    type Err = String;
+
+    /// Parses a platform shard identifier, accepting both the host form (`na1`) and common short
+    /// aliases (`na`), case-insensitively.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "na1" | "na" => Ok(Platform::Na1),
+            "br1" | "br" => Ok(Platform::Br1),
+            "la1" | "lan" => Ok(Platform::La1),
+            "la2" | "las" => Ok(Platform::La2),
+            "oc1" | "oce" => Ok(Platform::Oc1),
+            "euw1" | "euw" => Ok(Platform::Euw1),
+            "eun1" | "eune" => Ok(Platform::Eun1),
+            "tr1" | "tr" => Ok(Platform::Tr1),
+            "ru" => Ok(Platform::Ru),
+            "kr" => Ok(Platform::Kr),
+            "jp1" | "jp" => Ok(Platform::Jp1),
+            other => Err(format!("unknown platform region '{other}'")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_host_and_alias_forms() {
+        assert_eq!(Platform::from_str("na1"), Ok(Platform::Na1));
+        assert_eq!(Platform::from_str("NA"), Ok(Platform::Na1));
+        assert_eq!(Platform::from_str(" euw "), Ok(Platform::Euw1));
+    }
+
+    #[test]
+    fn rejects_unknown_platform() {
+        assert!(Platform::from_str("mars1").is_err());
+    }
+
+    #[test]
+    fn platforms_map_to_expected_regions() {
+        assert_eq!(Platform::Na1.region(), Region::Americas);
+        assert_eq!(Platform::Kr.region(), Region::Asia);
+        assert_eq!(Platform::Euw1.region(), Region::Europe);
+    }
+
+    #[test]
+    fn hosts_render_expected_labels() {
+        assert_eq!(Platform::Na1.host(), "na1");
+        assert_eq!(Platform::Na1.region().host(), "americas");
+    }
+}
@@ -0,0 +1,111 @@
+use std::fmt;
+
+use serde_derive::{Deserialize, Serialize};
+
+/// Defines a string newtype whose [`Debug`] representation hides the wrapped value.
+///
+/// The secret stays reachable through [`secret()`](Secret::secret), but a stray `debug!`/`info!` of
+/// a surrounding struct prints `[redacted]` instead of the credential. Each type is `#[serde(transparent)]`
+/// so YAML and environment parsing sees a plain string, exactly as before the wrapping.
+macro_rules! secret {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+        #[serde(transparent)]
+        pub struct $name(String);
+
+        impl $name {
+            /// Returns the underlying secret value.
+            ///
+            /// Call this only where the raw credential is genuinely needed — building an
+            /// `Authorization` header, signing a request — never when logging.
+            pub fn secret(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}([redacted])", stringify!($name))
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(value: String) -> Self {
+                $name(value)
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(value: &str) -> Self {
+                $name(value.to_string())
+            }
+        }
+    };
+}
+
+secret! {
+    /// The OAuth client secret used for Basic authentication at the token endpoint.
+    ClientSecret
+}
+
+secret! {
+    /// The Riot API key sent as the `X-Riot-Token` header.
+    ApiToken
+}
+
+secret! {
+    /// An OAuth access token returned by the authorization server.
+    AccessToken
+}
+
+secret! {
+    /// An OAuth refresh token returned by the authorization server.
+    RefreshToken
+}
+
+// Access and refresh tokens are rendered to the developer on the post-login page, so these two carry
+// a `Display` that reveals the value; the credentials that only ever flow server-side deliberately do
+// not, to keep them out of formatted strings entirely.
+impl fmt::Display for AccessToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Display for RefreshToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debug_hides_the_secret() {
+        let secret = ClientSecret::from("super-secret");
+        let rendered = format!("{secret:?}");
+        assert_eq!(rendered, "ClientSecret([redacted])");
+        assert!(!rendered.contains("super-secret"));
+    }
+
+    #[test]
+    fn secret_accessor_returns_the_value() {
+        assert_eq!(ApiToken::from("RGAPI-123").secret(), "RGAPI-123");
+    }
+
+    #[test]
+    fn display_reveals_rendered_tokens() {
+        assert_eq!(AccessToken::from("at").to_string(), "at");
+        assert_eq!(RefreshToken::from("rt").to_string(), "rt");
+    }
+
+    #[test]
+    fn serde_is_transparent() {
+        let token: ApiToken = serde_json::from_str("\"value\"").unwrap();
+        assert_eq!(token.secret(), "value");
+        assert_eq!(serde_json::to_string(&token).unwrap(), "\"value\"");
+    }
+}
@@ -1,8 +1,13 @@
+use std::sync::Arc;
+
 use crate::config::Configuration;
+use crate::state::AppState;
 use crate::{config, handlers};
 use axum::{routing::get, Router};
 use axum_server::tls_rustls::RustlsConfig;
 use log::{debug, info};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::ServerConfig;
 
 /// Creates an instance of `Router` configured with routes and application state.
 ///
@@ -16,11 +21,19 @@ use log::{debug, info};
 ///
 /// Returns an instance of `Router` configured with the application's routes and state.
 fn create_app(cfg: &Configuration) -> Router {
+    app_with_state(AppState::new(cfg.clone()))
+}
+
+/// Builds the router from an already-constructed [`AppState`].
+///
+/// Split out from [`create_app`] so that tests can seed the state (e.g. with a pre-existing session)
+/// before wiring up the routes.
+fn app_with_state(state: AppState) -> Router {
     Router::new()
         .route("/data", get(handlers::data::handle))
-        .route("/oauth", get(handlers::oauth::handle))
+        .route("/oauth/{provider}", get(handlers::oauth::handle))
         .route("/", get(handlers::default::handle))
-        .with_state(cfg.clone())
+        .with_state(state)
 }
 
 /// Starts the web service with the provided configuration.
@@ -41,9 +54,7 @@ pub(crate) async fn listen(cfg: &config::Configuration) {
             let app = create_app(cfg);
             match cfg.clone().server.tls {
                 Some(tls) => {
-                    let config = RustlsConfig::from_pem_file(tls.cert, tls.key)
-                        .await
-                        .unwrap();
+                    let config = load_rustls_config(&tls).unwrap_or_else(|e| panic!("{e}"));
                     info!("☁️ starting server with tls @ {addr}");
                     axum_server::bind_rustls(addr, config)
                         .serve(app.into_make_service())
@@ -65,15 +76,91 @@ pub(crate) async fn listen(cfg: &config::Configuration) {
     }
 }
 
+/// Loads the configured PEM certificate chain and private key into a rustls [`ServerConfig`].
+///
+/// Supports PKCS#8, RSA (PKCS#1) and SEC1 EC private keys. Returns a descriptive error when the
+/// certificate file yields no certificates or the key file no usable private key, so a misconfigured
+/// deployment fails with a clear message instead of serving nothing.
+fn load_rustls_config(tls: &config::Tls) -> Result<RustlsConfig, String> {
+    let certs = load_certs(&tls.cert)?;
+    let key = load_private_key(&tls.key)?;
+    let config = ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| format!("invalid TLS certificate/key pair: {e}"))?;
+    Ok(RustlsConfig::from_config(Arc::new(config)))
+}
+
+/// Reads and parses the PEM certificate chain at `path`, erroring if it contains no certificates.
+fn load_certs(path: &str) -> Result<Vec<CertificateDer<'static>>, String> {
+    let data = std::fs::read(path).map_err(|e| format!("error reading TLS certificate {path}: {e}"))?;
+    let certs = rustls_pemfile::certs(&mut data.as_slice())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("error parsing TLS certificate {path}: {e}"))?;
+    if certs.is_empty() {
+        return Err(format!("no certificate found in {path}"));
+    }
+    Ok(certs)
+}
+
+/// Reads and parses the first PEM private key at `path`, erroring if none is usable.
+fn load_private_key(path: &str) -> Result<PrivateKeyDer<'static>, String> {
+    let data = std::fs::read(path).map_err(|e| format!("error reading TLS key {path}: {e}"))?;
+    rustls_pemfile::private_key(&mut data.as_slice())
+        .map_err(|e| format!("error parsing TLS key {path}: {e}"))?
+        .ok_or_else(|| format!("no usable private key found in {path}"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::config::{Api, Rso, Tls, Urls};
     use axum::body::Body;
     use axum::http::{Request, StatusCode};
-    use mock::AuthProvider;
+    use crate::session::{Session, COOKIE_NAME};
+    use chrono::{Duration, Utc};
+    use mock::{ApiProvider, AuthProvider};
     use tower::ServiceExt;
 
+    /// Seeds a valid, unexpired session into `state` and returns its cookie id.
+    fn seed_session(state: &AppState) -> String {
+        state.sessions.insert(Session {
+            provider: crate::config::DEFAULT_PROVIDER.to_string(),
+            access_token: "token".to_string(),
+            refresh_token: "refresh".to_string(),
+            scope: "openid".to_string(),
+            expiry: Utc::now() + Duration::hours(1),
+        })
+    }
+
+    fn api_configuration(api: &ApiProvider) -> Configuration {
+        Configuration {
+            server: crate::config::Server {
+                addr: "0.0.0.0:443".to_string(),
+                tls: Some(Tls {
+                    cert: "cert".to_string(),
+                    key: "key".to_string(),
+                }),
+            },
+            api: Api {
+                token: "token".into(),
+                urls: Urls {
+                    account_data: api.server.url("/riot/account/v1/accounts/me"),
+                    champion_data: api.server.url("/lol/platform/v3/champion-rotations"),
+                },
+            },
+            oauth: std::collections::HashMap::from([(
+                crate::config::DEFAULT_PROVIDER.to_string(),
+                Rso {
+                    base_url: api.server.url("").to_string(),
+                    callback_host: "local.example.com:8080".to_string(),
+                    client_id: "client_id".to_string(),
+                    client_secret: "client_secret".into(),
+                },
+            )]),
+        }
+    }
+
     fn configuration(auth: &AuthProvider) -> Configuration {
         Configuration {
             server: crate::config::Server {
@@ -84,18 +171,21 @@ mod tests {
                 }),
             },
             api: Api {
-                token: "token".to_string(),
+                token: "token".into(),
                 urls: Urls {
                     account_data: auth.server.url("/riot/account/v1/accounts/me"),
                     champion_data: auth.server.url("/lol/platform/v3/champion-rotations"),
                 },
             },
-            rso: Rso {
-                base_url: auth.server.url("").to_string(),
-                callback_host: "local.example.com:8080".to_string(),
-                client_id: "client_id".to_string(),
-                client_secret: "client_secret".to_string(),
-            },
+            oauth: std::collections::HashMap::from([(
+                crate::config::DEFAULT_PROVIDER.to_string(),
+                Rso {
+                    base_url: auth.server.url("").to_string(),
+                    callback_host: "local.example.com:8080".to_string(),
+                    client_id: "client_id".to_string(),
+                    client_secret: "client_secret".into(),
+                },
+            )]),
         }
     }
     #[tokio::test]
@@ -119,7 +209,7 @@ mod tests {
         let response = app
             .oneshot(
                 Request::builder()
-                    .uri("/oauth")
+                    .uri("/oauth/default")
                     .body(Body::empty())
                     .unwrap(),
             )
@@ -132,13 +222,14 @@ mod tests {
     #[tokio::test]
     async fn oauth_code() {
         let prov = mock::AuthProvider::new();
-        let cfg = configuration(&prov);
-        let app = create_app(&cfg);
+        let state = AppState::new(configuration(&prov));
+        state.pkce.insert("csrf".to_string(), crate::pkce::generate_verifier());
+        let app = app_with_state(state);
 
         let response = app
             .oneshot(
                 Request::builder()
-                    .uri("/oauth?code=200")
+                    .uri("/oauth/default?code=200&state=csrf")
                     .body(Body::empty())
                     .unwrap(),
             )
@@ -149,7 +240,7 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn data_returns_expected_result() {
+    async fn oauth_unknown_state_is_rejected() {
         let prov = mock::AuthProvider::new();
         let cfg = configuration(&prov);
         let app = create_app(&cfg);
@@ -157,7 +248,28 @@ mod tests {
         let response = app
             .oneshot(
                 Request::builder()
-                    .uri("/data?access_token=200")
+                    .uri("/oauth/default?code=200&state=unknown")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn data_returns_expected_result() {
+        let prov = mock::ApiProvider::new();
+        let state = AppState::new(api_configuration(&prov));
+        let id = seed_session(&state);
+        let app = app_with_state(state);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/data")
+                    .header("Cookie", format!("{COOKIE_NAME}={id}"))
                     .body(Body::empty())
                     .unwrap(),
             )
@@ -168,9 +280,38 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn data_returns_unauthorized_when_no_access_token() {
-        let prov = mock::AuthProvider::new();
-        let cfg = configuration(&prov);
+    async fn data_returns_json_when_negotiated() {
+        let prov = mock::ApiProvider::new();
+        let state = AppState::new(api_configuration(&prov));
+        let id = seed_session(&state);
+        let app = app_with_state(state);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/data")
+                    .header("Cookie", format!("{COOKIE_NAME}={id}"))
+                    .header("Accept", "application/json")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response
+                .headers()
+                .get("content-type")
+                .and_then(|v| v.to_str().ok()),
+            Some("application/json")
+        );
+    }
+
+    #[tokio::test]
+    async fn data_returns_unauthorized_when_no_session() {
+        let prov = mock::ApiProvider::new();
+        let cfg = api_configuration(&prov);
         let app = create_app(&cfg);
 
         let response = app
@@ -178,6 +319,36 @@ mod tests {
             .await
             .unwrap();
 
-        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn data_returns_unauthorized_when_session_is_unknown() {
+        let prov = mock::ApiProvider::new();
+        let cfg = api_configuration(&prov);
+        let app = create_app(&cfg);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/data")
+                    .header("Cookie", format!("{COOKIE_NAME}=bogus"))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn tls_config_errors_clearly_when_files_are_missing() {
+        let tls = Tls {
+            cert: "/nonexistent/cert.pem".to_string(),
+            key: "/nonexistent/key.pem".to_string(),
+        };
+        let err = load_rustls_config(&tls).unwrap_err();
+        assert!(err.contains("error reading TLS certificate"), "{err}");
     }
 }
@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use base64::prelude::*;
+use chrono::{DateTime, Duration, Utc};
+use rand::RngCore;
+
+use crate::config::Configuration;
+use crate::handlers::oauth;
+use crate::http::Client;
+use crate::token::{self, TokenSet};
+
+/// The name of the opaque session cookie handed to the browser after a successful login.
+pub const COOKIE_NAME: &str = "session";
+
+/// A server-side OAuth session.
+///
+/// Holds the full token set returned by the RSO token endpoint, keyed in the [`SessionStore`] by an
+/// opaque cookie value. Keeping the tokens server-side means they never travel in request URLs or
+/// logs, and the stored `refresh_token` lets the session outlive the one-hour access-token lifetime.
+#[derive(Clone, Debug)]
+pub struct Session {
+    /// The RSO provider this session was established against, so a later refresh targets the same
+    /// environment.
+    pub provider: String,
+    /// The current OAuth access token.
+    pub access_token: String,
+    /// The refresh token used to mint a fresh access token once the current one expires.
+    pub refresh_token: String,
+    /// The granted scope.
+    pub scope: String,
+    /// The absolute instant at which `access_token` expires.
+    pub expiry: DateTime<Utc>,
+}
+
+impl Session {
+    /// Builds a session from a freshly exchanged token response for `provider`, turning the relative
+    /// `expires_in` into an absolute expiry instant.
+    pub fn from_response(res: &oauth::Response, provider: String) -> Self {
+        Session {
+            provider,
+            access_token: res.access_token.secret().to_string(),
+            refresh_token: res.refresh_token.secret().to_string(),
+            scope: res.scope.clone(),
+            expiry: Utc::now() + Duration::seconds(res.expires_in as i64),
+        }
+    }
+
+    /// Whether the access token has reached or passed its expiry.
+    pub fn is_expired(&self) -> bool {
+        Utc::now() >= self.expiry
+    }
+}
+
+/// A thread-safe store mapping opaque session ids to their [`Session`].
+///
+/// Clones share the same underlying map, so a single store lives in the Axum application state and
+/// is seen by every handler, mirroring [`RateLimiter`](crate::rate_limit::RateLimiter).
+#[derive(Clone, Default)]
+pub struct SessionStore {
+    inner: Arc<Mutex<HashMap<String, Session>>>,
+}
+
+impl SessionStore {
+    /// Creates an empty store.
+    pub fn new() -> Self {
+        SessionStore::default()
+    }
+
+    /// Stores `session` under a freshly generated opaque id and returns that id.
+    pub fn insert(&self, session: Session) -> String {
+        let id = new_session_id();
+        self.inner.lock().unwrap().insert(id.clone(), session);
+        id
+    }
+
+    /// Returns the session stored under `id`, if any.
+    pub fn get(&self, id: &str) -> Option<Session> {
+        self.inner.lock().unwrap().get(id).cloned()
+    }
+
+    /// Replaces the session stored under `id`, e.g. after a refresh.
+    pub fn update(&self, id: &str, session: Session) {
+        self.inner.lock().unwrap().insert(id.to_string(), session);
+    }
+}
+
+/// Generates a 256-bit opaque session identifier, URL-safe base64 encoded without padding.
+fn new_session_id() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    BASE64_URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Extracts the session id from the value of a `Cookie` header, if present.
+pub fn session_id_from_cookies(header: &str) -> Option<String> {
+    header.split(';').find_map(|pair| {
+        let (name, value) = pair.trim().split_once('=')?;
+        (name == COOKIE_NAME).then(|| value.to_string())
+    })
+}
+
+/// Refreshes a session's tokens at the RSO token endpoint.
+///
+/// Delegates to [`token::refresh`] for the actual `refresh_token` grant and repackages the resulting
+/// [`TokenSet`] as a [`Session`] for storage.
+pub async fn refresh(
+    client: &dyn Client,
+    cfg: &Configuration,
+    provider: &str,
+    refresh_token: &str,
+) -> Result<Session, String> {
+    let token: TokenSet = token::refresh(client, cfg, provider, refresh_token).await?;
+    Ok(Session {
+        provider: provider.to_string(),
+        access_token: token.access_token,
+        refresh_token: token.refresh_token,
+        scope: token.scope,
+        expiry: token.expiry,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(expiry: DateTime<Utc>) -> Session {
+        Session {
+            provider: "default".to_string(),
+            access_token: "access".to_string(),
+            refresh_token: "refresh".to_string(),
+            scope: "openid".to_string(),
+            expiry,
+        }
+    }
+
+    #[test]
+    fn is_expired_tracks_the_expiry_instant() {
+        assert!(session(Utc::now() - Duration::seconds(1)).is_expired());
+        assert!(!session(Utc::now() + Duration::seconds(60)).is_expired());
+    }
+
+    #[test]
+    fn store_round_trips_a_session() {
+        let store = SessionStore::new();
+        let id = store.insert(session(Utc::now() + Duration::hours(1)));
+        assert_eq!(store.get(&id).unwrap().access_token, "access");
+        assert!(store.get("unknown").is_none());
+    }
+
+    #[test]
+    fn session_ids_are_unique() {
+        let store = SessionStore::new();
+        let first = store.insert(session(Utc::now() + Duration::hours(1)));
+        let second = store.insert(session(Utc::now() + Duration::hours(1)));
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn session_id_is_parsed_from_cookie_header() {
+        assert_eq!(
+            session_id_from_cookies("foo=bar; session=abc123; baz=qux"),
+            Some("abc123".to_string())
+        );
+        assert_eq!(session_id_from_cookies("foo=bar"), None);
+    }
+}
@@ -0,0 +1,89 @@
+use std::sync::Arc;
+
+use axum::extract::FromRef;
+
+use crate::auth::TokenCache;
+use crate::config::Configuration;
+use crate::http::{Client, ReqwestClient};
+use crate::pkce::PkceStore;
+use crate::rate_limit::RateLimiter;
+use crate::session::SessionStore;
+
+/// The shared application state handed to every Axum handler.
+///
+/// The configuration is immutable once parsed, while the [`RateLimiter`] carries interior,
+/// self-correcting state that is shared across every request. Grouping them behind a single state
+/// value lets handlers extract just the piece they need via [`FromRef`], keeping the existing
+/// `State<Configuration>` handlers working unchanged.
+#[derive(Clone)]
+pub struct AppState {
+    /// The parsed application configuration.
+    pub cfg: Configuration,
+    /// The shared rate limiter that throttles outbound Riot API calls.
+    pub rate_limiter: RateLimiter,
+    /// The HTTP client used for all outbound Riot API calls.
+    pub client: Arc<dyn Client>,
+    /// Cache of introspected access tokens to their resolved accounts.
+    pub token_cache: TokenCache,
+    /// Server-side store of OAuth sessions, keyed by an opaque cookie value.
+    pub sessions: SessionStore,
+    /// Pending PKCE `(state -> code_verifier)` mappings for in-flight authorization flows.
+    pub pkce: PkceStore,
+}
+
+impl AppState {
+    /// Builds the application state from a parsed [`Configuration`], using the default
+    /// `reqwest`-backed HTTP client.
+    pub fn new(cfg: Configuration) -> Self {
+        AppState::with_client(cfg, Arc::new(ReqwestClient::new()))
+    }
+
+    /// Builds the application state with a caller-supplied HTTP client, letting tests inject a mock
+    /// implementation in place of the default `reqwest` client.
+    pub fn with_client(cfg: Configuration, client: Arc<dyn Client>) -> Self {
+        AppState {
+            cfg,
+            rate_limiter: RateLimiter::new(),
+            client,
+            token_cache: TokenCache::new(),
+            sessions: SessionStore::new(),
+            pkce: PkceStore::new(),
+        }
+    }
+}
+
+impl FromRef<AppState> for Configuration {
+    fn from_ref(state: &AppState) -> Self {
+        state.cfg.clone()
+    }
+}
+
+impl FromRef<AppState> for RateLimiter {
+    fn from_ref(state: &AppState) -> Self {
+        state.rate_limiter.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn Client> {
+    fn from_ref(state: &AppState) -> Self {
+        state.client.clone()
+    }
+}
+
+impl FromRef<AppState> for TokenCache {
+    fn from_ref(state: &AppState) -> Self {
+        state.token_cache.clone()
+    }
+}
+
+impl FromRef<AppState> for SessionStore {
+    fn from_ref(state: &AppState) -> Self {
+        state.sessions.clone()
+    }
+}
+
+impl FromRef<AppState> for PkceStore {
+    fn from_ref(state: &AppState) -> Self {
+        state.pkce.clone()
+    }
+}
@@ -0,0 +1,113 @@
+use base64::prelude::*;
+use chrono::{DateTime, Duration, Utc};
+use log::debug;
+
+use crate::config::Configuration;
+use crate::handlers::oauth;
+use crate::http::Client;
+
+/// A captured OAuth token set together with everything needed to keep it alive.
+///
+/// Unlike the bare `access_token` string the flow used to pass around, this type remembers the
+/// refresh token and an absolute expiry, so a caller can notice when the token has aged out and mint
+/// a fresh one via [`refresh`] without sending the user back through the browser.
+#[derive(Clone, Debug)]
+pub struct TokenSet {
+    /// The current access token.
+    pub access_token: String,
+    /// The refresh token used to obtain a new access token once this one expires.
+    pub refresh_token: String,
+    /// The granted scope.
+    pub scope: String,
+    /// The absolute instant at which `access_token` expires.
+    pub expiry: DateTime<Utc>,
+}
+
+impl TokenSet {
+    /// Builds a token from a token-endpoint response, turning the relative `expires_in` into an
+    /// absolute expiry instant.
+    pub fn from_response(res: &oauth::Response) -> Self {
+        TokenSet {
+            access_token: res.access_token.secret().to_string(),
+            refresh_token: res.refresh_token.secret().to_string(),
+            scope: res.scope.clone(),
+            expiry: Utc::now() + Duration::seconds(res.expires_in as i64),
+        }
+    }
+
+    /// Whether the token should be considered expired.
+    ///
+    /// The `leeway` is subtracted from the expiry so callers refresh slightly early, avoiding a race
+    /// where a token that passes the check here expires before the request it authorizes lands.
+    pub fn is_expired(&self, leeway: Duration) -> bool {
+        Utc::now() + leeway >= self.expiry
+    }
+}
+
+/// Exchanges a refresh token for a fresh [`TokenSet`] at `provider`'s RSO token endpoint.
+///
+/// POSTs `grant_type=refresh_token` with the Basic client credentials to
+/// [`Configuration::token_url`], which is the normal way to keep a long-lived RSO session alive.
+pub async fn refresh(
+    client: &dyn Client,
+    cfg: &Configuration,
+    provider: &str,
+    refresh_token: &str,
+) -> Result<TokenSet, String> {
+    debug!("🔄 exchanging refresh token");
+    let rso = cfg
+        .rso(provider)
+        .ok_or_else(|| format!("unknown provider '{provider}'"))?;
+    let token_url = cfg
+        .token_url(provider)
+        .ok_or_else(|| format!("unknown provider '{provider}'"))?;
+    let auth = BASE64_STANDARD.encode(format!(
+        "{}:{}",
+        rso.client_id,
+        rso.client_secret.secret()
+    ));
+    let res = client
+        .post_form(
+            &token_url,
+            vec![("Authorization".into(), format!("Basic {auth}"))],
+            cfg.refresh_token_request(refresh_token),
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    if !(200..300).contains(&res.status()) {
+        return Err(format!("refresh failed with status {}", res.status()));
+    }
+    let response: oauth::Response = res.into_json().await.map_err(|e| e.to_string())?;
+    Ok(TokenSet::from_response(&response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(expires_in: u32) -> oauth::Response {
+        oauth::Response {
+            access_token: "access".into(),
+            refresh_token: "refresh".into(),
+            scope: "openid".to_string(),
+            id_token: "id".to_string(),
+            token_type: "Bearer".to_string(),
+            expires_in,
+        }
+    }
+
+    #[test]
+    fn from_response_computes_expiry() {
+        let token = TokenSet::from_response(&response(3600));
+        assert!(token.expiry > Utc::now());
+        assert_eq!(token.refresh_token, "refresh");
+    }
+
+    #[test]
+    fn is_expired_respects_leeway() {
+        // Expires in 30s: live with no leeway, already expired with a 60s leeway.
+        let token = TokenSet::from_response(&response(30));
+        assert!(!token.is_expired(Duration::zero()));
+        assert!(token.is_expired(Duration::seconds(60)));
+    }
+}